@@ -65,6 +65,14 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        zip(self.left.iter(), self.right.iter())
+            .map(|(l, r)| format!("{}   {}\n", l, r))
+            .collect()
+    }
+}
+
 fn parse_two_lists(input: &str) -> IResult<&str, Day> {
     map_res(
         terminated(separated_list1(newline, separated_pair(parse_usize, space1, parse_usize)), newline),
@@ -80,6 +88,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -115,4 +125,15 @@ mod tests {
     fn test_similarity_score(example_parsed: Day) {
         assert_eq!(example_parsed.similarity_score(), 31)
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
@@ -1,19 +1,24 @@
 use crate::prelude::*;
 
-use std::str::FromStr;
-
-use rayon::prelude::*;
+use crate::repl::{unknown, Inspect};
 
-use crate::table::{parse_char_table, shift, TableDir, TableIdx, from_pattern, into_shape};
+use std::str::FromStr;
 
-use petgraph::{algo, prelude::*};
+use anyhow::anyhow;
 
-use itertools::iproduct;
+use crate::table::{parse_char_table, Cells, Dir4, Grid, PPCharTable, TableIdx};
 
-type TrailGraph = DiGraph<(), ()>;
+use petgraph::prelude::*;
 
-use crate::graph::{NodeMap, get_node_or_insert};
+type TrailGraph = DiGraph<u8, ()>;
 
+use crate::graph::{
+    dag::{path_counts, reachable_counts},
+    export::{components, position_labels, Export},
+    from_grid,
+    pathfinding::bfs,
+    DenseNodeMap, PositionMap,
+};
 
 impl AoC for Day {
     fn run(input: &str) -> anyhow::Result<AoCResult> {
@@ -26,74 +31,146 @@ impl AoC for Day {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+struct Trailhead {
+    pos: TableIdx,
+    /// Number of peaks reachable from here.
+    score: usize,
+    /// Number of distinct trails to any peak.
+    rating: usize,
+}
+
 #[derive(Debug)]
 pub struct Day {
-    trails: Vec<(TableIdx, TableIdx, usize)>
+    table: Grid<u8>,
+    trailheads: Vec<Trailhead>,
 }
 
-const ALL_CARD_DIRS: [TableDir; 4] = [
-    TableDir::new(0, 1),
-    TableDir::new(0, -1),
-    TableDir::new(1, 0),
-    TableDir::new(-1, 0),
-];
-
 impl Day {
     fn part_a(&self) -> usize {
-        self.trails.len()
+        self.trailheads.iter().map(|t| t.score).sum()
     }
 
     fn part_b(&self) -> usize {
-        self.trails.iter().map(|x| x.2).sum()
+        self.trailheads.iter().map(|t| t.rating).sum()
     }
 }
 
+/// Edges lead from every tile to the neighbors one higher. Every step climbs by one, so the
+/// graph has no cycles.
+fn trail_graph(table: &Grid<u8>) -> (TrailGraph, DenseNodeMap) {
+    from_grid(table, Dir4::ALL, |from, to| (from + 1 == *to).then_some(()))
+}
 
 impl FromStr for Day {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Day> {
-        let table = (parse_char_table(s)?).map(|x| x.to_digit(10).unwrap() as u8);
-        let mut nodemap = NodeMap::new();
-        let mut graph = TrailGraph::new();
-        let mut trailheads = Vec::new();
-        let mut peaks = Vec::new();
-
-        for (x, height) in table.indexed_iter() {
-            let curpos = from_pattern(x);
-            let curnode = get_node_or_insert(&curpos, &mut graph, &mut nodemap);
-            nodemap.insert(curnode, curpos);
-            if height == &0 {
-                trailheads.push(curnode);
-            } else if height == &9 {
-                peaks.push(curnode);
-            }
+        let table = Grid::from(parse_char_table(s)?).try_map(|c| {
+            c.to_digit(10)
+                .map(|d| d as u8)
+                .ok_or(anyhow!("{} is not a digit", c))
+        })?;
+        let (graph, nodemap) = trail_graph(&table);
+        let node = |pos| nodemap.node(&pos).unwrap();
+        let peaks: Vec<NodeIndex> = table.find_all(|h| *h == 9).map(node).collect();
+        let scores = reachable_counts(&graph, &peaks)?;
+        let ratings = path_counts(&graph, &peaks)?;
 
-            for dir in &ALL_CARD_DIRS {
-                let neighborpos = shift(curpos, *dir);
-                if let Some(neighborheight) = table.get(into_shape(neighborpos)) {
-                    if height + 1 == *neighborheight {
-                        let neighbornode = get_node_or_insert(&neighborpos, &mut graph, &mut nodemap);
-                        graph.add_edge(curnode, neighbornode, ());
-                    }
-                }
-            }
+        let trailheads = graph
+            .node_indices()
+            .filter(|head| graph[*head] == 0)
+            .map(|head| Trailhead {
+                pos: nodemap.position(head).unwrap(),
+                score: scores[head.index()],
+                rating: ratings[head.index()],
+            })
+            .collect();
+
+        Ok(Day {
+            table,
+            trailheads,
+        })
+    }
+}
+
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        let mut ret = String::new();
+        for row in self.table.rows() {
+            ret.extend(row.map(|h| char::from_digit(*h as u32, 10).expect("heights are 0-9")));
+            ret.push('\n');
         }
+        ret
+    }
+}
 
-        let trails = iproduct!(trailheads.iter(), peaks.iter()).par_bridge().filter_map(|(head, peak)| {
+pub struct Inspector {
+    day: Day,
+}
 
-            let ways = algo::all_simple_paths::<Vec<_>,_>(&graph, *head, *peak, 1, None).collect::<Vec<_>>();
+impl Inspector {
+    /// The heights of all tiles on trails starting at `head`, with `.` everywhere else.
+    fn trail_tiles(&self, head: TableIdx) -> Grid<char> {
+        let table = &self.day.table;
+        let climbs = |pos: &TableIdx| {
+            let height = table[*pos];
+            table
+                .neighbors4(*pos)
+                .filter(move |(_, next_height)| **next_height == height + 1)
+                .map(|(next, _)| next)
+        };
+        let reachable = bfs([head], climbs, |_| false);
+        let mut tiles = table.map(|_| '.');
+        for (pos, height) in table.indexed_iter().filter(|(pos, _)| reachable.cost(pos).is_some()) {
+            tiles[pos] = char::from_digit(*height as u32, 10).unwrap();
+        }
+        tiles
+    }
+}
 
-            if ways.is_empty() {
-                None
-            } else {
-                Some((*nodemap.get_by_left(head).unwrap(), *nodemap.get_by_left(peak).unwrap(), ways.len()))
-            }
-        }).collect();
+impl Inspect for Inspector {
+    fn load(input: &str) -> anyhow::Result<Self> {
+        Ok(Inspector { day: input.parse()? })
+    }
 
-        Ok(Day {
-            trails,
-        })
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("trailheads", "number of trailheads"),
+            ("trailhead X Y", "score and rating of the trailhead at X Y and the tiles its trails cover"),
+            ("export FILE", "write the trail graph as .dot or .graphml, clustered by connected area"),
+        ]
+    }
+
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+        match (command, args) {
+            ("trailheads", []) => Ok(self.day.trailheads.len().to_string()),
+            ("trailhead", [x, y]) => {
+                let pos = TableIdx::new(x.parse()?, y.parse()?);
+                let trailhead = self
+                    .day
+                    .trailheads
+                    .iter()
+                    .find(|t| t.pos == pos)
+                    .ok_or(anyhow!("there is no trailhead at {} {}", x, y))?;
+
+                Ok(format!(
+                    "score {}, rating {}{:?}",
+                    trailhead.score,
+                    trailhead.rating,
+                    PPCharTable::from(&self.trail_tiles(pos))
+                ))
+            }
+            ("export", [file]) => {
+                let (graph, nodemap) = trail_graph(&self.day.table);
+                let areas = components(&graph);
+                Export::new(&graph, position_labels(&nodemap))
+                    .clusters(|node, _| Some(format!("area {}", areas[node.index()])))
+                    .save(file)?;
+                Ok(format!("wrote {} tiles and {} steps to {}", graph.node_count(), graph.edge_count(), file))
+            }
+            _ => Err(unknown(command, args)),
+        }
     }
 }
 
@@ -102,6 +179,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn small_example() -> &'static str {
         "\
@@ -136,8 +215,50 @@ mod tests {
         assert_eq!(larger_example.parse::<Day>().unwrap().part_a(), 36);
     }
 
+    #[rstest]
+    fn trailhead_scores(larger_example: &'static str) {
+        let day: Day = larger_example.parse().unwrap();
+        let scores: Vec<usize> = day.trailheads.iter().map(|t| t.score).collect();
+        let ratings: Vec<usize> = day.trailheads.iter().map(|t| t.rating).collect();
+        assert_eq!(scores, [5, 6, 5, 3, 1, 3, 5, 3, 5]);
+        assert_eq!(ratings, [20, 24, 10, 4, 1, 4, 5, 8, 5]);
+        assert_eq!(day.trailheads[0].pos, TableIdx::new(2, 0));
+    }
+
     #[rstest]
     fn test_larger_example_b(larger_example: &'static str) {
         assert_eq!(larger_example.parse::<Day>().unwrap().part_b(), 81);
     }
+
+    #[rstest]
+    fn inspect_trailhead(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        assert_eq!(inspector.exec("trailheads", &[]).unwrap(), "1");
+        assert_eq!(
+            inspector.exec("trailhead", &["0", "0"]).unwrap(),
+            "score 1, rating 16\n0123\n1234\n8765\n9876\n"
+        );
+        assert!(inspector.exec("trailhead", &["1", "0"]).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("trails.dot");
+        let file = file.to_str().unwrap();
+        assert_eq!(
+            inspector.exec("export", &[file]).unwrap(),
+            format!("wrote 16 tiles and 21 steps to {}", file)
+        );
+        let dot = std::fs::read_to_string(file).unwrap();
+        assert!(dot.contains("subgraph cluster_0 {\n        label=\"area 0\";\n        n0 [label=\"0,0\"];"));
+    }
+
+    #[rstest]
+    fn round_trip(larger_example: &'static str) {
+        let parsed: Day = larger_example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), larger_example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(larger_example: &'static str) {
+        check_parser::<Day>(larger_example)
+    }
 }
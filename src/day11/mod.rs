@@ -4,6 +4,8 @@ use std::str::FromStr;
 
 use std::collections::HashMap;
 
+use itertools::Itertools;
+
 type StoneMap = HashMap<usize, usize>;
 
 use nom::{
@@ -68,6 +70,18 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        let stones = self
+            .stones
+            .iter()
+            .sorted()
+            .flat_map(|(stone, number)| std::iter::repeat_n(stone, *number))
+            .join(" ");
+        format!("{}\n", stones)
+    }
+}
+
 fn parse_day(input: &str) -> IResult<&str, Day> {
     map_res(
         terminated(separated_list1(tag(" "), parse_usize), newline),
@@ -127,6 +141,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -151,4 +167,15 @@ mod tests {
     fn test_part_a(example_parsed: Day) {
         assert_eq!(example_parsed.part_a(), 55312)
     }
+
+    #[rstest]
+    fn round_trip(example_parsed: Day) {
+        let reparsed: Day = example_parsed.to_puzzle_string().parse().unwrap();
+        assert_eq!(reparsed, example_parsed)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
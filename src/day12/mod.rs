@@ -1,14 +1,19 @@
 use crate::prelude::*;
 
-use std::str::FromStr;
+use crate::repl::{parse_arg, unknown, Inspect};
+
+use crate::graph::{
+    export::{position_labels, Export},
+    from_grid, DenseNodeMap, PositionMap,
+};
 
-use crate::table::{from_pattern, into_shape, parse_char_table, shift, CharTable, TableDir};
+use petgraph::prelude::*;
 
-use crate::graph::{get_node_or_insert, NodeMap};
+use std::str::FromStr;
 
-use petgraph::{algo::kosaraju_scc, prelude::*};
+use crate::table::{parse_char_table, regions, Canvas, Dir4, Grid, PPCharTable, Regions, Rgb, TableIdx};
 
-type PlotGraph = UnGraph<(), ()>;
+use anyhow::anyhow;
 
 impl AoC for Day {
     fn run(input: &str) -> anyhow::Result<AoCResult> {
@@ -21,124 +26,19 @@ impl AoC for Day {
     }
 }
 
-const ALL_CARD_DIRS: [TableDir; 4] = [
-    TableDir::new(0, 1),
-    TableDir::new(0, -1),
-    TableDir::new(1, 0),
-    TableDir::new(-1, 0),
-];
-
-const ALL_DIAGS: [TableDir; 4] = [
-    TableDir::new(1, 1),
-    TableDir::new(-1, -1),
-    TableDir::new(1, -1),
-    TableDir::new(-1, 1),
-];
-
 #[derive(Debug)]
 pub struct Day {
-    table: CharTable,
-    graph: PlotGraph,
-    regions: Vec<Vec<NodeIndex>>,
-    nodemap: NodeMap,
+    table: Grid<char>,
+    regions: Regions,
 }
 
 impl Day {
     fn part_a(&self) -> usize {
-        self.regions
-            .iter()
-            .map(|r| {
-                let area = r.len();
-                let perimeter: usize = r
-                    .iter()
-                    .map(|n| ALL_CARD_DIRS.len() - self.graph.edges(*n).count())
-                    .sum();
-
-                area * perimeter
-            })
-            .sum()
+        self.regions.iter().map(|r| r.area() * r.perimeter).sum()
     }
 
     fn part_b(&self) -> usize {
-        self.regions
-            .iter()
-            .enumerate()
-            .map(|(idx, r)| {
-                let area = r.len();
-                let corners: usize = r
-                    .iter()
-                    .map(|node| {
-                        let nodepos = self.nodemap.get_by_left(node).unwrap();
-                        let neighbordirs: Vec<TableDir> = self
-                            .graph
-                            .neighbors(*node)
-                            .map(|neigh| {
-                                let neighborpos = self.nodemap.get_by_left(&neigh).unwrap();
-                                neighborpos.as_() - nodepos.as_()
-                            })
-                            .collect();
-
-                        let check_diag = |diag| {
-                            let diagpos = shift(*nodepos, diag);
-                            let diag = self.table.get(into_shape(diagpos))?;
-
-                            if !self.regions[idx]
-                                .contains(self.nodemap.get_by_right(&diagpos).unwrap())
-                            {
-                                return Some(false);
-                            }
-
-                            Some(self.table.get(into_shape(*nodepos)).unwrap() == diag)
-                        };
-
-                        let count_different_diag_regions = |x: &[TableDir]| -> usize {
-                            x
-                                .iter()
-                                .filter_map(|dir| {
-                                    if let Some(false) = check_diag(*dir) {
-                                        Some(())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .count()};
-
-                        match neighbordirs.len() {
-                            0 => 4,
-                            1 => 2,
-                            2 => {
-                                if neighbordirs[0].dot(neighbordirs[1]) == 0 {
-                                    if check_diag(neighbordirs[0] + neighbordirs[1]).unwrap() {
-                                        1
-                                    } else {
-                                        2
-                                    }
-                                } else {
-                                    0
-                                }
-                            }
-                            3 => {
-                                let (center, tail) = match (
-                                    neighbordirs[0].dot(neighbordirs[1]).abs(),
-                                    neighbordirs[0].dot(neighbordirs[2]).abs(),
-                                ) {
-                                    (0,0) => (neighbordirs[0], neighbordirs[1]),
-                                    (1,0) => (neighbordirs[2], neighbordirs[0]),
-                                    (0,1) => (neighbordirs[1], neighbordirs[0]),
-                                        _ => unreachable!()
-                                };
-
-                                count_different_diag_regions(&[center + tail, center - tail])
-                            }
-                            4 => count_different_diag_regions(&ALL_DIAGS),
-                            _ => unreachable!(),
-                        }
-                    })
-                    .sum();
-
-                area * corners
-            })
-            .sum()
+        self.regions.iter().map(|r| r.area() * r.sides).sum()
     }
 }
 
@@ -146,32 +46,96 @@ impl FromStr for Day {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Day> {
-        let table = parse_char_table(s)?;
-        let mut graph: PlotGraph = Graph::new_undirected();
-        let mut nodemap = NodeMap::new();
-
-        for (x, curplot) in table.indexed_iter() {
-            let curpos = from_pattern(x);
-            let curnode = get_node_or_insert(&curpos, &mut graph, &mut nodemap);
-
-            for dir in &ALL_CARD_DIRS {
-                let neighborpos = shift(curpos, *dir);
-                if let Some(neighborplot) = table.get(into_shape(neighborpos)) {
-                    if curplot == neighborplot {
-                        let neighbornode =
-                            get_node_or_insert(&neighborpos, &mut graph, &mut nodemap);
-                        graph.update_edge(curnode, neighbornode, ());
-                    }
+        let table: Grid<char> = parse_char_table(s)?.into();
+        let regions = regions(&table);
+        Ok(Day { table, regions })
+    }
+}
+
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.table.to_puzzle_string()
+    }
+}
+
+pub struct Inspector {
+    day: Day,
+}
+
+impl Inspector {
+    fn label(&self, x: &str, y: &str) -> anyhow::Result<usize> {
+        let pos = TableIdx::new(x.parse()?, y.parse()?);
+        self.day
+            .regions
+            .label(pos)
+            .ok_or(anyhow!("{} {} is outside of the garden", x, y))
+    }
+
+    /// Every region in its own color, with all but the `highlight`ed one dimmed.
+    fn canvas(&self, highlight: Option<usize>) -> Canvas {
+        let labels = self.day.regions.labels();
+        let canvas = Canvas::new(labels, |label| Rgb::pick(*label));
+        match highlight {
+            Some(highlight) => {
+                let others = labels.indexed_iter().filter(|(_, label)| **label != highlight);
+                canvas.tint(others.map(|(pos, _)| pos), Rgb::BLACK)
+            }
+            None => canvas,
+        }
+    }
+}
+
+impl Inspect for Inspector {
+    fn load(input: &str) -> anyhow::Result<Self> {
+        Ok(Inspector { day: input.parse()? })
+    }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("region X Y", "show the region containing the plot at X Y"),
+            ("regions", "number of regions"),
+            ("paint X Y", "draw all regions in color, highlighting the one containing X Y"),
+            ("save FILE [SCALE]", "write a .png or .ppm picture of all regions, SCALE pixels per plot"),
+            ("export FILE", "write the graph of neighboring equal plots as .dot or .graphml, clustered by region"),
+        ]
+    }
+
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+        match (command, args) {
+            ("region", [x, y]) => {
+                let region = self.day.regions.get(self.label(x, y)?).unwrap();
+
+                let mut table = self.day.table.map(|_| '.');
+                for pos in &region.cells {
+                    table[*pos] = self.day.table[*pos];
                 }
+
+                Ok(format!(
+                    "area {}, perimeter {}, sides {}, holes {}{:?}",
+                    region.area(),
+                    region.perimeter,
+                    region.sides,
+                    region.holes,
+                    PPCharTable::from(&table)
+                ))
             }
+            ("regions", []) => Ok(self.day.regions.len().to_string()),
+            ("paint", [x, y]) => Ok(self.canvas(Some(self.label(x, y)?)).to_ansi()),
+            ("save", [file, scale @ ..]) => {
+                self.canvas(None).save(file, parse_arg(scale, 4)?)?;
+                Ok(format!("saved {}", file))
+            }
+            ("export", [file]) => {
+                let (graph, nodemap): (UnGraph<char, ()>, DenseNodeMap) =
+                    from_grid(&self.day.table, Dir4::ALL, |from, to| (from == to).then_some(()));
+                let region = |node| nodemap.position(node).and_then(|pos| self.day.regions.label(pos));
+                Export::new(&graph, position_labels(&nodemap))
+                    .clusters(|node, plant| region(node).map(|label| format!("{} {}", plant, label)))
+                    .save(file)?;
+                Ok(format!("wrote {} plots to {}", graph.node_count(), file))
+            }
+            _ => Err(unknown(command, args)),
         }
-        let regions = kosaraju_scc(&graph);
-        Ok(Day {
-            table,
-            graph,
-            regions,
-            nodemap,
-        })
     }
 }
 
@@ -180,6 +144,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn small_example() -> &'static str {
         "\
@@ -273,4 +239,52 @@ mod tests {
     fn test_ab_example_part_b(ab_example_parsed: Day) {
         assert_eq!(ab_example_parsed.part_b(), 368)
     }
+
+    #[rstest]
+    fn inspect_region(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        assert_eq!(inspector.exec("regions", &[]).unwrap(), "5");
+        assert_eq!(
+            inspector.exec("region", &["2", "1"]).unwrap(),
+            "area 4, perimeter 10, sides 8, holes 0\n....\n..C.\n..CC\n...C\n"
+        )
+    }
+
+    #[rstest]
+    fn inspect_paint(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        let painted = inspector.exec("paint", &["2", "1"]).unwrap();
+        assert_eq!(painted.lines().count(), 2);
+        // Only the four plots of region C keep their own color.
+        let c = Rgb::pick(2);
+        let own = format!("8;2;{};{};{}m", c.r, c.g, c.b);
+        assert_eq!(painted.matches(&own).count(), 4);
+        assert_eq!(
+            inspector.exec("paint", &["9", "1"]).unwrap_err().to_string(),
+            "9 1 is outside of the garden"
+        );
+    }
+
+    #[rstest]
+    fn inspect_export(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("plots.graphml");
+        let file = file.to_str().unwrap();
+        assert_eq!(inspector.exec("export", &[file]).unwrap(), format!("wrote 16 plots to {}", file));
+        let xml = std::fs::read_to_string(file).unwrap();
+        assert_eq!(xml.matches("<data key=\"cluster\">C 2</data>").count(), 4);
+        assert!(xml.contains("edgedefault=\"undirected\""));
+    }
+
+    #[rstest]
+    fn round_trip(small_example: &'static str) {
+        let parsed: Day = small_example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), small_example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(small_example: &'static str) {
+        check_parser::<Day>(small_example)
+    }
 }
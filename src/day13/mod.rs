@@ -6,6 +6,8 @@ use std::str::FromStr;
 
 use crate::table::TableIdx;
 
+use itertools::Itertools;
+
 use ndarray::prelude::*;
 use ndarray_linalg::*;
 
@@ -77,6 +79,20 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.machines
+            .iter()
+            .map(|m| {
+                format!(
+                    "Button A: X+{}, Y+{}\nButton B: X+{}, Y+{}\nPrize: X={}, Y={}\n",
+                    m.a.x, m.a.y, m.b.x, m.b.y, m.prize.x, m.prize.y
+                )
+            })
+            .join("\n")
+    }
+}
+
 fn parse_day(input: &str) -> IResult<&str, Day> {
     map_res(
         terminated(separated_list1(count(newline, 2), parse_machine), newline),
@@ -151,6 +167,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     use vek::Vec2;
 
     #[fixture]
@@ -213,4 +231,15 @@ mod tests {
     fn test_part_a(example_parsed: Day) {
         assert_eq!(example_parsed.part_a(), 480)
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
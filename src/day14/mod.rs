@@ -1,8 +1,12 @@
 use crate::prelude::*;
 
+use crate::repl::{parse_arg, unknown, Inspect};
+
 use std::str::FromStr;
 
-use crate::table::{TableDir, TableIdx, PPCharTable, CharTable, into_shape};
+use crate::table::{Grid, PPCharTable, Recorder, Rgb, Symmetry, TableDir, TableIdx, Torus};
+
+use ndarray::Array2;
 
 use nom::{
     bytes::complete::tag,
@@ -31,26 +35,28 @@ pub struct Day {
 }
 
 impl Day {
-    fn bathroom(&self, width: usize, height: usize) -> Bathroom {
-        Bathroom {
-            robots: self.robots.clone(),
-            width,
-            height,
+    fn bathroom(&self, width: usize, height: usize) -> anyhow::Result<Bathroom> {
+        let mut floor: Torus<usize> = Grid::from(Array2::zeros((height, width))).try_into()?;
+        let mut robots = self.robots.clone();
+        for robot in &mut robots {
+            robot.walk(&floor, 0);
+            floor[robot.p] += 1;
         }
+        Ok(Bathroom { robots, floor })
     }
 
     fn part_a(&self) -> usize {
-        let mut bathroom = self.bathroom(101, 103);
+        let mut bathroom = self.bathroom(101, 103).expect("the bathroom has cells");
         bathroom.walk(100);
         bathroom.quadrants().safety_factor()
     }
 
     fn part_b(&self) -> usize {
-        let mut bathroom = self.bathroom(101, 103);
+        let mut bathroom = self.bathroom(101, 103).expect("the bathroom has cells");
         let mut min_safety_factor = usize::MAX;
         let mut ret = 0;
         let mut tree = String::new();
-        for cur in 1..bathroom.width*bathroom.height {
+        for cur in 1..bathroom.floor.width() * bathroom.floor.height() {
             bathroom.walk(1);
             let safety_factor = bathroom.quadrants().safety_factor();
             if safety_factor < min_safety_factor {
@@ -84,6 +90,15 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.robots
+            .iter()
+            .map(|r| format!("p={},{} v={},{}\n", r.p.x, r.p.y, r.v.x, r.v.y))
+            .collect()
+    }
+}
+
 fn parse_day(input: &str) -> IResult<&str, Day> {
     map_res(
         terminated(
@@ -123,33 +138,25 @@ struct Robot {
     v: TableDir,
 }
 
+#[derive(Clone)]
 struct Bathroom {
     robots: Vec<Robot>,
-    width: usize,
-    height: usize,
+    /// Number of robots on every tile.
+    floor: Torus<usize>,
 }
 
 impl std::fmt::Display for Bathroom {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut table = CharTable::from_shape_simple_fn((self.height, self.width), || '.');
-
-        for robot in &self.robots {
-            let elem = table.get_mut(into_shape(robot.p)).unwrap();
-            match elem {
-                '.' => *elem = '1',
-                ref val if val.is_ascii_hexdigit() => *elem = char::from_digit(val.to_digit(16).unwrap() + 1, 16).unwrap(),
-                _ => unreachable!()
-            }
-        }
+        let mut table = self.counts();
 
         let center = self.center();
 
-        for y in 0..self.height {
-            *table.get_mut(into_shape(TableIdx::new(center.x, y))).unwrap() = ' ';
+        for y in 0..table.height() {
+            table[TableIdx::new(center.x, y)] = ' ';
         }
 
-        for x in 0..self.width {
-            *table.get_mut(into_shape(TableIdx::new(x, center.y))).unwrap() = ' ';
+        for x in 0..table.width() {
+            table[TableIdx::new(x, center.y)] = ' ';
         }
 
         write!(f, "{:?}", PPCharTable::from(&table))
@@ -157,14 +164,24 @@ impl std::fmt::Display for Bathroom {
 }
 
 impl Bathroom {
+    /// The number of robots on every tile, in hex.
+    fn counts(&self) -> Grid<char> {
+        self.floor.as_grid().map(|count| match count {
+            0 => '.',
+            n => char::from_digit(*n as u32, 16).unwrap(),
+        })
+    }
+
     fn walk(&mut self, duration: isize) {
-        self.robots
-            .iter_mut()
-            .for_each(|r| r.walk(self.width, self.height, duration));
+        for robot in &mut self.robots {
+            self.floor[robot.p] -= 1;
+            robot.walk(&self.floor, duration);
+            self.floor[robot.p] += 1;
+        }
     }
 
     fn center(&self) -> TableIdx {
-        TableIdx::new((self.width - 1) / 2, (self.height - 1) / 2)
+        TableIdx::new((self.floor.width() - 1) / 2, (self.floor.height() - 1) / 2)
     }
 
     fn quadrant_idx(&self, pos: &TableIdx) -> Option<usize> {
@@ -195,10 +212,8 @@ impl Bathroom {
 }
 
 impl Robot {
-    fn walk(&mut self, width: usize, height: usize, duration: isize) {
-        let beeg = self.p.as_::<i64>() + duration as i64 * self.v.as_::<i64>();
-        self.p = TableIdx::new(beeg.x.rem_euclid(width.try_into().unwrap()) as usize, beeg.y.rem_euclid(height.try_into().unwrap()) as usize);
-
+    fn walk<T>(&mut self, floor: &Torus<T>, duration: isize) {
+        self.p = floor.wrap(self.p.as_() + self.v * duration);
     }
 }
 
@@ -212,11 +227,95 @@ impl Quadrants {
 }
 
 
+pub struct Inspector {
+    day: Day,
+    bathroom: Bathroom,
+    seconds: isize,
+}
+
+impl Inspector {
+    fn show(&self) -> String {
+        format!("after {} seconds:{}", self.seconds, &self.bathroom)
+    }
+}
+
+impl Inspect for Inspector {
+    fn load(input: &str) -> anyhow::Result<Self> {
+        let day: Day = input.parse()?;
+        let bathroom = day.bathroom(101, 103)?;
+        Ok(Inspector {
+            day,
+            bathroom,
+            seconds: 0,
+        })
+    }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("advance [T]", "let T seconds pass (default 1, may be negative) and show the robots"),
+            ("show", "show the robots"),
+            ("tiled", "show the bathroom four times, so pictures crossing its edges are whole"),
+            ("record FILE T [N]", "record the next T seconds, every Nth one, as .cast, .gif or PNGs in directory FILE"),
+            ("safety", "safety factor of the current positions"),
+            ("symmetries", "the ways to turn or mirror the bathroom that leave the robots where they are"),
+            ("size W H", "resize the bathroom and start over"),
+        ]
+    }
+
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+        match (command, args) {
+            ("advance", _) => {
+                let duration = parse_arg(args, 1)?;
+                self.bathroom.walk(duration);
+                self.seconds += duration;
+                Ok(self.show())
+            }
+            ("show", []) => Ok(self.show()),
+            ("tiled", []) => Ok(format!(
+                "after {} seconds:{:?}",
+                self.seconds,
+                PPCharTable::from(&self.bathroom.counts().tile(2, 2))
+            )),
+            ("record", [file, duration, every @ ..]) => {
+                let mut bathroom = self.bathroom.clone();
+                let mut recorder = Recorder::default().every(parse_arg(every, 1)?);
+                recorder.push(&bathroom.counts())?;
+                for _ in 0..duration.parse()? {
+                    bathroom.walk(1);
+                    recorder.push(&bathroom.counts())?;
+                }
+                let recording = recorder.finish();
+                let palette = |count: &char| if *count == '.' { Rgb::BLACK } else { Rgb::GREEN };
+                recording.save(file, 10, 2, palette, |count| *count)?;
+                Ok(format!("recorded {} frames to {}", recording.frames().len(), file))
+            }
+            ("safety", []) => Ok(self.bathroom.quadrants().safety_factor().to_string()),
+            ("symmetries", []) => {
+                let counts = self.bathroom.counts();
+                Ok(Symmetry::ALL
+                    .iter()
+                    .filter(|s| counts.transform(**s) == counts)
+                    .map(|s| format!("{:?}", s))
+                    .collect::<Vec<_>>()
+                    .join(" "))
+            }
+            ("size", [width, height]) => {
+                self.bathroom = self.day.bathroom(width.parse()?, height.parse()?)?;
+                self.seconds = 0;
+                Ok(self.show())
+            }
+            _ => Err(unknown(command, args)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -300,9 +399,67 @@ mod tests {
 
     #[rstest]
     fn test_bathroom(example_parsed: Day) {
-        let mut bathroom = example_parsed.bathroom(11, 7);
+        let mut bathroom = example_parsed.bathroom(11, 7).unwrap();
         bathroom.walk(100);
         assert_eq!(bathroom.quadrants().safety_factor(), 12)
 
     }
+
+    #[rstest]
+    fn inspect_advance(example: &'static str) {
+        let mut inspector = Inspector::load(example).unwrap();
+        inspector.exec("size", &["11", "7"]).unwrap();
+        inspector.exec("advance", &["150"]).unwrap();
+        inspector.exec("advance", &["-50"]).unwrap();
+        assert_eq!(inspector.exec("safety", &[]).unwrap(), "12");
+        assert!(inspector.exec("size", &["0", "7"]).is_err())
+    }
+
+    #[rstest]
+    fn inspect_tiled(example: &'static str) {
+        let mut inspector = Inspector::load(example).unwrap();
+        inspector.exec("size", &["11", "7"]).unwrap();
+        let show = inspector.exec("show", &[]).unwrap();
+        let tiled = inspector.exec("tiled", &[]).unwrap();
+        assert_eq!(show.lines().count(), 8);
+        assert_eq!(tiled.lines().count(), 15);
+        assert!(tiled.contains("1.12.......1.12......."));
+        assert!(show.contains("1.12. .....\n"));
+    }
+
+    #[rstest]
+    fn inspect_symmetries(example: &'static str) {
+        let mut inspector = Inspector::load(example).unwrap();
+        inspector.exec("size", &["11", "7"]).unwrap();
+        assert_eq!(inspector.exec("symmetries", &[]).unwrap(), "Identity");
+
+        let mut inspector = Inspector::load("p=1,0 v=0,1\np=1,2 v=0,1\n").unwrap();
+        inspector.exec("size", &["3", "3"]).unwrap();
+        assert_eq!(inspector.exec("symmetries", &[]).unwrap(), "Identity RotateHalf FlipHorizontal FlipVertical");
+    }
+
+    #[rstest]
+    fn inspect_record(example: &'static str) {
+        let mut inspector = Inspector::load(example).unwrap();
+        inspector.exec("size", &["11", "7"]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("run.gif");
+        let file = file.to_str().unwrap();
+        // Seconds 0, 7, ..., 77 are kept; the robots are back where they started at the end.
+        let recorded = inspector.exec("record", &[file, "77", "7"]).unwrap();
+        assert_eq!(recorded, format!("recorded 12 frames to {}", file));
+        assert!(std::fs::metadata(file).unwrap().len() > 0);
+        assert!(inspector.exec("show", &[]).unwrap().starts_with("after 0 seconds"));
+    }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
@@ -1,31 +1,15 @@
 use crate::prelude::*;
 
+use crate::repl::{parse_arg, unknown, Inspect};
+
 use std::str::FromStr;
 
 use anyhow::anyhow;
 
-use bimap::BiMap;
-
-use crate::table::{
-    from_pattern, into_shape, parse_char_table, shift, CharTable, PPCharTable, TableDir, TableIdx,
-};
+use crate::table::{cast_ray, diff, parse_grid, Dir4, Grid, PPCharTable, Recorder, Rgb, TableIdx};
 
 use nom::{bytes::complete::take_until, error::Error, Finish};
 
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref CHAR_MAP: BiMap<char, TableDir> = BiMap::from_iter(
-        [
-            ('<', TableDir::new(-1, 0)),
-            ('^', TableDir::new(0, -1)),
-            ('>', TableDir::new(1, 0)),
-            ('v', TableDir::new(0, 1)),
-        ]
-        .into_iter()
-    );
-}
-
 impl AoC for Day {
     fn run(input: &str) -> anyhow::Result<AoCResult> {
         let parsed: Day = input.parse()?;
@@ -37,91 +21,111 @@ impl AoC for Day {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct Day {
-    table: CharTable,
-    moves: Vec<TableDir>,
+/// The part of a box a tile holds, boxes in the widened warehouse span two tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BoxPart {
+    Whole,
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Empty,
+    Robot,
+    Box(BoxPart),
 }
 
-fn gps_coordinates(table: &CharTable) -> impl Iterator<Item = (TableIdx, usize)> + '_ {
-    table.indexed_iter().filter_map(|(pattern, c)| {
-        if c == &'O' || c == &'[' {
-            let pos = from_pattern(pattern);
-            return Some((pos, pos.x + pos.y * 100));
+impl TryFrom<char> for Tile {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, char> {
+        match c {
+            '#' => Ok(Tile::Wall),
+            '.' => Ok(Tile::Empty),
+            '@' => Ok(Tile::Robot),
+            'O' => Ok(Tile::Box(BoxPart::Whole)),
+            '[' => Ok(Tile::Box(BoxPart::Left)),
+            ']' => Ok(Tile::Box(BoxPart::Right)),
+            c => Err(c),
         }
-        None
-    })
+    }
 }
 
-fn thicc_table(table: &CharTable) -> CharTable {
-    let mut cells = Vec::new();
-    for cell in table.iter() {
-        let ex: &str = match *cell {
-            '#' => "##",
-            'O' => "[]",
-            '.' => "..",
-            '@' => "@.",
-            _ => unreachable!(),
-        };
-        cells.extend(ex.chars());
+impl From<Tile> for char {
+    fn from(tile: Tile) -> char {
+        match tile {
+            Tile::Wall => '#',
+            Tile::Empty => '.',
+            Tile::Robot => '@',
+            Tile::Box(BoxPart::Whole) => 'O',
+            Tile::Box(BoxPart::Left) => '[',
+            Tile::Box(BoxPart::Right) => ']',
+        }
     }
-    let original_shape = table.dim();
-    CharTable::from_shape_vec((original_shape.0, 2 * original_shape.1), cells).unwrap()
 }
 
-fn find_start_pos(table: &CharTable) -> TableIdx {
-    for (pattern, c) in table.indexed_iter() {
-        if c == &'@' {
-            return from_pattern(pattern);
+impl Tile {
+    /// The two tiles this one turns into in the doubled warehouse.
+    fn widen(self) -> [Tile; 2] {
+        match self {
+            Tile::Wall => [Tile::Wall, Tile::Wall],
+            Tile::Empty => [Tile::Empty, Tile::Empty],
+            Tile::Robot => [Tile::Robot, Tile::Empty],
+            Tile::Box(_) => [Tile::Box(BoxPart::Left), Tile::Box(BoxPart::Right)],
         }
     }
-    panic!("couldn't find start position");
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Day {
+    table: Grid<Tile>,
+    moves: Vec<Dir4>,
+}
+
+fn gps_coordinates(table: &Grid<Tile>) -> impl Iterator<Item = (TableIdx, usize)> + '_ {
+    table.indexed_iter().filter_map(|(pos, tile)| match tile {
+        Tile::Box(BoxPart::Whole | BoxPart::Left) => Some((pos, pos.x + pos.y * 100)),
+        _ => None,
+    })
+}
+
+fn thicc_table(table: &Grid<Tile>) -> Grid<Tile> {
+    table.scale(2, 1, |tile| tile.widen())
 }
 
 impl Day {
     fn part_a(&self) -> usize {
         let mut table = self.table.clone();
-        let it = self.walk(&mut table);
-        for _afterpos in it {
-            //dbg!(&afterpos);
-        }
+        self.walk(&mut table).for_each(drop);
         gps_coordinates(&table).map(|(_pos, gps)| gps).sum()
     }
 
     fn part_b(&self) -> usize {
         let mut table = thicc_table(&self.table);
-        let it = self.thicc_walk(&mut table);
-        for _afterpos in it {
-            //dbg!(&afterpos);
-        }
+        self.thicc_walk(&mut table).for_each(drop);
         gps_coordinates(&table).map(|(_pos, gps)| gps).sum()
     }
 
-    fn walk<'a>(&'a self, table: &'a mut CharTable) -> Robot<'a> {
-        let pos = find_start_pos(table);
-        let last_board = table.clone();
-        Robot {
-            table,
-            pos,
-            dirs: self.moves.clone().into_iter().rev().collect(),
-            shape: Vec::new(),
-            visit: Vec::new(),
-            last_board
-        }
+    fn walk<'a>(&'a self, table: &'a mut Grid<Tile>) -> Robot<'a> {
+        self.resume(table, 0)
     }
 
-    fn thicc_walk<'a>(&'a self, table: &'a mut CharTable) -> Robot<'a> {
-        let pos = find_start_pos(table);
-        let last_board = table.clone();
+    fn resume<'a>(&'a self, table: &'a mut Grid<Tile>, done: usize) -> Robot<'a> {
+        let pos = table.find(|tile| *tile == Tile::Robot).expect("warehouse has a robot");
         Robot {
             table,
             pos,
-            dirs: self.moves.clone().into_iter().rev().collect(),
+            dirs: self.moves[done..].iter().rev().copied().collect(),
             shape: Vec::new(),
             visit: Vec::new(),
-            last_board
         }
     }
+
+    fn thicc_walk<'a>(&'a self, table: &'a mut Grid<Tile>) -> Robot<'a> {
+        self.resume(table, 0)
+    }
 }
 
 impl FromStr for Day {
@@ -130,34 +134,70 @@ impl FromStr for Day {
     fn from_str(s: &str) -> anyhow::Result<Day> {
         let (input, tablestring) = take_until("\n\n")(s)
             .finish()
-            .map_err(|Error { input, code }| anyhow!("input: {}, code: {:?}", input, code))
-            .unwrap();
-        let table = parse_char_table(tablestring)?;
+            .map_err(|Error { input, code }| anyhow!("input: {}, code: {:?}", input, code))?;
+        let table: Grid<Tile> = parse_grid(tablestring)?;
+        if table.find_all(|tile| *tile == Tile::Robot).count() != 1 {
+            return Err(anyhow!("warehouse needs exactly one robot"));
+        }
         let moves = input
             .chars()
-            .filter_map(|c| {
-                let x = CHAR_MAP.get_by_left(&c)?;
-                Some(*x)
-            })
+            .filter_map(|c| Dir4::try_from(c).ok())
             .collect();
         Ok(Day { table, moves })
     }
 }
 
+const MOVES_PER_LINE: usize = 70;
+
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        let mut ret = self.table.to_puzzle_string();
+        ret.push('\n');
+        for line in self.moves.chunks(MOVES_PER_LINE) {
+            ret.extend(line.iter().map(|dir| char::from(*dir)));
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 struct Elem {
     pos: TableIdx,
-    shape: char,
+    shape: Tile,
 }
 
 #[derive(Debug)]
 struct Robot<'a> {
-    table: &'a mut CharTable,
+    table: &'a mut Grid<Tile>,
     pos: TableIdx,
-    dirs: Vec<TableDir>,
+    dirs: Vec<Dir4>,
     shape: Vec<Elem>,
     visit: Vec<Elem>,
-    last_board: CharTable,
+}
+
+impl Robot<'_> {
+    /// Adds `elem` to the moving shape and queues the tile in front of it, unless that is
+    /// off the board.
+    fn push_ahead(&mut self, elem: Elem, dir: Dir4) -> Option<()> {
+        let look = self.table.shift(elem.pos, dir.to_vec())?;
+        self.shape.push(elem);
+        self.visit.push(Elem {
+            pos: look,
+            shape: self.table[look],
+        });
+        Some(())
+    }
+
+    /// Pushes both halves of a wide box, `side` pointing from `half` to the `other` one.
+    fn push_box(&mut self, half: Elem, side: Dir4, other: BoxPart, dir: Dir4) -> Option<()> {
+        let comrade = Elem {
+            pos: self.table.shift(half.pos, side.to_vec())?,
+            shape: Tile::Box(other),
+        };
+        self.push_ahead(half, dir)?;
+        self.push_ahead(comrade, dir)
+    }
 }
 
 impl<'a> Iterator for Robot<'a> {
@@ -165,92 +205,177 @@ impl<'a> Iterator for Robot<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let dir = self.dirs.pop()?;
-        let dirchar = *CHAR_MAP.get_by_right(&dir).unwrap();
         self.shape.clear();
         self.visit.clear();
-        //dbg!(&dirchar);
 
         self.visit.push(Elem {
             pos: self.pos,
-            shape: '@',
+            shape: Tile::Robot,
         });
         let mut can_move = true;
         while let Some(cur) = self.visit.pop() {
-            match (cur.shape, dirchar) {
-                ('@' | 'O', _) | ('[' | ']', '<' | '>')  => {
-                    self.shape.push(cur);
-                    let look = shift(cur.pos, dir);
-                    self.visit.push(Elem {
-                        pos: look,
-                        shape: *self.table.get(into_shape(look)).unwrap(),
-                    });
+            let pushed = match (cur.shape, dir.is_horizontal()) {
+                (Tile::Robot | Tile::Box(BoxPart::Whole), _) | (Tile::Box(_), true) => {
+                    self.push_ahead(cur, dir)
                 }
-                ('#', _) => {
-                    can_move = false;
-                    break;
-                }
-                ('.', _) => {}
-                ('[', _)  => {
-                    self.shape.push(cur);
-                    let look = shift(cur.pos, dir);
-                    self.visit.push(Elem {
-                        pos: look,
-                        shape: *self.table.get(into_shape(look)).unwrap(),
-                    });
-                    let comrade = Elem { pos : shift(cur.pos, TableDir::new(1, 0)), shape : ']' };
-                    self.shape.push(comrade);
-                    let look = shift(comrade.pos, dir);
-                    self.visit.push(Elem {
-                        pos: look,
-                        shape: *self.table.get(into_shape(look)).unwrap(),
-                    });
-                }
-                (']', _)  => {
-                    self.shape.push(cur);
-                    let look = shift(cur.pos, dir);
-                    self.visit.push(Elem {
-                        pos: look,
-                        shape: *self.table.get(into_shape(look)).unwrap(),
-                    });
-                    let comrade = Elem { pos : shift(cur.pos, TableDir::new(-1, 0)), shape : '[' };
-                    self.shape.push(comrade);
-                    let look = shift(comrade.pos, dir);
-                    self.visit.push(Elem {
-                        pos: look,
-                        shape: *self.table.get(into_shape(look)).unwrap(),
-                    });
-                }
-                _ => unreachable!(),
+                (Tile::Wall, _) => None,
+                (Tile::Empty, _) => Some(()),
+                (Tile::Box(BoxPart::Left), _) => self.push_box(cur, Dir4::Right, BoxPart::Right, dir),
+                (Tile::Box(BoxPart::Right), _) => self.push_box(cur, Dir4::Left, BoxPart::Left, dir),
+            };
+            if pushed.is_none() {
+                can_move = false;
+                break;
             }
         }
-        let make_print = self.shape.len() > 5;
         if can_move {
             while let Some(cur) = self.shape.pop() {
-                let look = shift(cur.pos, dir);
-                *self.table.get_mut(into_shape(look)).unwrap() = cur.shape;
-                *self.table.get_mut(into_shape(cur.pos)).unwrap() = '.';
+                let look = self.table.shift(cur.pos, dir.to_vec()).expect("checked while visiting");
+                *self.table.get_mut(look).unwrap() = cur.shape;
+                *self.table.get_mut(cur.pos).unwrap() = Tile::Empty;
                 if self.shape.is_empty() {
                     self.pos = look;
                 }
             }
         }
-        let pptable = self.table.clone();
-        if make_print {
-            let pp: PPCharTable = (&pptable).into();
-            let pp_last: PPCharTable = (&self.last_board).into();
-            dbg!(&pp_last);
-            dbg!(&dirchar, &pp);
-        }
-        self.last_board = pptable;
         Some(self.pos)
     }
 }
 
+pub struct Inspector {
+    day: Day,
+    table: Grid<Tile>,
+    wide: bool,
+    done: usize,
+}
+
+impl Inspector {
+    fn reset(&mut self) {
+        self.table = if self.wide {
+            thicc_table(&self.day.table)
+        } else {
+            self.day.table.clone()
+        };
+        self.done = 0;
+    }
+
+    fn board(&self) -> String {
+        format!(
+            "{}/{} moves{:?}",
+            self.done,
+            self.day.moves.len(),
+            PPCharTable::from(&self.table.to_chars())
+        )
+    }
+
+    /// Moves the robot `steps` times and returns the board from before.
+    fn advance(&mut self, steps: usize) -> Grid<Tile> {
+        let before = self.table.clone();
+        self.done += self.day.resume(&mut self.table, self.done).take(steps).count();
+        before
+    }
+}
+
+impl Inspect for Inspector {
+    fn load(input: &str) -> anyhow::Result<Self> {
+        let day: Day = input.parse()?;
+        let table = day.table.clone();
+        Ok(Inspector {
+            day,
+            table,
+            wide: false,
+            done: 0,
+        })
+    }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("step [N]", "move the robot N times (default 1) and show the board, highlighting what moved"),
+            ("diff [N]", "move the robot N times (default 1) and show the boards before and after"),
+            ("board", "show the board"),
+            ("gps", "sum of the GPS coordinates of all boxes"),
+            ("look D", "tiles from the robot to the next wall or free tile in direction D (^>v<)"),
+            ("record FILE", "record the remaining moves as .cast, .gif or PNGs in directory FILE"),
+            ("wide", "toggle the doubled warehouse of part b and start over"),
+            ("reset", "start over with the initial board"),
+        ]
+    }
+
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+        match command {
+            "step" => {
+                let before = self.advance(parse_arg(args, 1)?);
+                let diff = diff(&before, &self.table);
+                Ok(format!("{}/{} moves\n{}", self.done, self.day.moves.len(), diff.highlighted()))
+            }
+            "diff" => {
+                let before = self.advance(parse_arg(args, 1)?);
+                let diff = diff(&before, &self.table);
+                Ok(format!("{} tiles changed\n{}", diff.changes().len(), diff.side_by_side()))
+            }
+            "board" => Ok(self.board()),
+            "gps" => Ok(gps_coordinates(&self.table)
+                .map(|(_pos, gps)| gps)
+                .sum::<usize>()
+                .to_string()),
+            "look" => {
+                let [dir] = args else {
+                    return Err(unknown(command, args));
+                };
+                let dir = dir
+                    .parse::<char>()
+                    .ok()
+                    .and_then(|c| Dir4::try_from(c).ok())
+                    .ok_or(anyhow!("expected one of ^>v<, got {:?}", dir))?;
+                let robot = self.table.find(|tile| *tile == Tile::Robot).expect("warehouse has a robot");
+                Ok(cast_ray(&self.table, robot.as_(), dir.to_vec())
+                    .stop_at(|tile| matches!(tile, Tile::Wall | Tile::Empty))
+                    .map(|(_, tile)| char::from(*tile))
+                    .collect())
+            }
+            "record" => {
+                let [file] = args else {
+                    return Err(unknown(command, args));
+                };
+                let mut table = self.table.clone();
+                let mut robot = self.day.resume(&mut table, self.done);
+                let mut recorder = Recorder::default();
+                recorder.push(robot.table)?;
+                while robot.next().is_some() {
+                    recorder.push(robot.table)?;
+                }
+                let recording = recorder.finish();
+                let palette = |tile: &Tile| match tile {
+                    Tile::Wall => Rgb::GRAY,
+                    Tile::Empty => Rgb::BLACK,
+                    Tile::Robot => Rgb::RED,
+                    Tile::Box(_) => Rgb::YELLOW,
+                };
+                recording.save(file, 10, 4, palette, |tile| char::from(*tile))?;
+                Ok(format!("recorded {} frames to {}", recording.frames().len(), file))
+            }
+            "wide" => {
+                self.wide = !self.wide;
+                self.reset();
+                Ok(self.board())
+            }
+            "reset" => {
+                self.reset();
+                Ok(self.board())
+            }
+            _ => Err(unknown(command, args)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+    use crate::table::assert_grid_eq;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -337,4 +462,112 @@ mod tests {
     fn test_part_b(#[case] test: Day, #[case] expected: usize) {
         assert_eq!(test.part_b(), expected)
     }
+
+    #[rstest]
+    fn final_boards(small_example_parsed: Day, example_parsed: Day) {
+        let mut table = small_example_parsed.table.clone();
+        small_example_parsed.walk(&mut table).for_each(drop);
+        let expected: Grid<Tile> = parse_grid(
+            "\
+            ########\n\
+            #....OO#\n\
+            ##.....#\n\
+            #.....O#\n\
+            #.#O@..#\n\
+            #...O..#\n\
+            #...O..#\n\
+            ########\n\
+            ",
+        )
+        .unwrap();
+        assert_grid_eq(&table, &expected);
+
+        let mut table = thicc_table(&example_parsed.table);
+        example_parsed.thicc_walk(&mut table).for_each(drop);
+        let expected: Grid<Tile> = parse_grid(
+            "\
+            ####################\n\
+            ##[].......[].[][]##\n\
+            ##[]...........[].##\n\
+            ##[]........[][][]##\n\
+            ##[]......[]....[]##\n\
+            ##..##......[]....##\n\
+            ##..[]............##\n\
+            ##..@......[].[][]##\n\
+            ##......[][]..[]..##\n\
+            ####################\n\
+            ",
+        )
+        .unwrap();
+        assert_grid_eq(&table, &expected);
+    }
+
+    #[rstest]
+    fn inspect_diff(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        inspector.exec("step", &["4"]).unwrap();
+        assert_eq!(
+            inspector.exec("diff", &[]).unwrap(),
+            "\
+            3 tiles changed\n\
+            before    after\n\
+            ########  ########\n\
+            #..@OO.#  #...@OO#  <\n\
+            ##..O..#  ##..O..#\n\
+            #...O..#  #...O..#\n\
+            #.#.O..#  #.#.O..#\n\
+            #...O..#  #...O..#\n\
+            #......#  #......#\n\
+            ########  ########\n\
+            "
+        );
+        let step = inspector.exec("step", &[]).unwrap();
+        assert!(step.starts_with("6/15 moves\n"));
+        assert!(!step.contains("\x1b[7m"));
+        assert!(inspector.exec("step", &[]).unwrap().contains("#...\x1b[7m.\x1b[0m"));
+    }
+
+    #[rstest]
+    fn inspect_steps(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        inspector.exec("step", &["5"]).unwrap();
+        assert!(inspector.exec("step", &["10"]).unwrap().starts_with("15/15 moves"));
+        assert_eq!(inspector.exec("gps", &[]).unwrap(), "2028")
+    }
+
+    #[rstest]
+    fn inspect_record(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        inspector.exec("step", &["3"]).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("run.cast");
+        let file = file.to_str().unwrap();
+        let recorded = inspector.exec("record", &[file]).unwrap();
+        // Of the 12 remaining moves three run into a wall and leave the board as it was.
+        assert_eq!(recorded, format!("recorded 10 frames to {}", file));
+        let cast = std::fs::read_to_string(file).unwrap();
+        assert_eq!(cast.lines().count(), 11);
+        assert!(cast.starts_with(r#"{"version": 2, "width": 8, "height": 8}"#));
+        assert!(inspector.exec("board", &[]).unwrap().starts_with("3/15 moves"));
+    }
+
+    #[rstest]
+    fn inspect_look(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        assert_eq!(inspector.exec("look", &["<"]).unwrap(), "@#");
+        inspector.exec("step", &["4"]).unwrap();
+        assert_eq!(inspector.exec("look", &[">"]).unwrap(), "@OO.");
+        assert!(inspector.exec("look", &["x"]).is_err());
+    }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
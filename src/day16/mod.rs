@@ -1,51 +1,237 @@
 use crate::prelude::*;
 
+use crate::repl::{parse_arg, unknown, Inspect};
+
 use std::str::FromStr;
 
-use crate::table::{
-    from_pattern, into_shape, parse_char_table, shift, CharTable, PPCharTable, TableDir, TableIdx,
-};
+use anyhow::anyhow;
 
-use std::collections::VecDeque;
+use itertools::Itertools;
 
+use crate::graph::pathfinding::{dijkstra, ShortestPaths};
+use crate::table::{parse_grid, Canvas, Dir4, Grid, PPCharTable, Rgb, TableIdx};
 
 impl AoC for Day {
     fn run(input: &str) -> anyhow::Result<AoCResult> {
         let parsed: Day = input.parse()?;
 
         Ok(AoCResult {
-            part_a : None,
-            part_b : None
+            part_a : parsed.part_a(),
+            part_b : Some(parsed.part_b())
         })
     }
 }
 
+const STEP_COST: usize = 1;
+const TURN_COST: usize = 1000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Tile {
+    Wall,
+    Open,
+    Start,
+    End,
+}
+
+impl TryFrom<char> for Tile {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, char> {
+        match c {
+            '#' => Ok(Tile::Wall),
+            '.' => Ok(Tile::Open),
+            'S' => Ok(Tile::Start),
+            'E' => Ok(Tile::End),
+            c => Err(c),
+        }
+    }
+}
+
+impl From<Tile> for char {
+    fn from(tile: Tile) -> char {
+        match tile {
+            Tile::Wall => '#',
+            Tile::Open => '.',
+            Tile::Start => 'S',
+            Tile::End => 'E',
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+struct Reindeer {
+    pos: TableIdx,
+    dir: Dir4,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Day {
-    table : CharTable
+    table : Grid<Tile>,
+    start: TableIdx,
 }
 
 impl Day {
+    fn moves(&self, reindeer: &Reindeer) -> impl Iterator<Item = (Reindeer, usize)> {
+        let ahead = self
+            .table
+            .shift(reindeer.pos, reindeer.dir.to_vec())
+            .filter(|pos| self.table[*pos] != Tile::Wall)
+            .map(|pos| (Reindeer { pos, ..*reindeer }, STEP_COST));
+        let turns = [reindeer.dir.turn_left(), reindeer.dir.turn_right()]
+            .map(|dir| (Reindeer { dir, ..*reindeer }, TURN_COST));
+        ahead.into_iter().chain(turns)
+    }
+
+    fn paths(&self) -> ShortestPaths<Reindeer, usize> {
+        let start = Reindeer {
+            pos: self.start,
+            dir: Dir4::Right,
+        };
+        dijkstra(
+            [start],
+            |reindeer| self.moves(reindeer),
+            |reindeer| self.table[reindeer.pos] == Tile::End,
+        )
+    }
+
+    /// The tiles on any of the best paths.
+    fn best_seats(paths: &ShortestPaths<Reindeer, usize>) -> impl Iterator<Item = TableIdx> {
+        paths
+            .on_shortest_paths(paths.goals())
+            .into_iter()
+            .map(|reindeer| reindeer.pos)
+            .unique()
+    }
+
+    fn part_a(&self) -> Option<usize> {
+        self.paths().goal_cost()
+    }
+
+    fn part_b(&self) -> usize {
+        Day::best_seats(&self.paths()).count()
+    }
 }
 
 impl FromStr for Day {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Day> {
-        let table = parse_char_table(s)?;
-        Ok(Day { table })
+        let table: Grid<Tile> = parse_grid(s)?;
+        let start = table
+            .find(|tile| *tile == Tile::Start)
+            .ok_or(anyhow!("maze has no start"))?;
+        if table.find(|tile| *tile == Tile::End).is_none() {
+            return Err(anyhow!("maze has no end"));
+        }
+        Ok(Day { table, start })
+    }
+}
+
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.table.to_puzzle_string()
+    }
+}
+
+pub struct Inspector {
+    day: Day,
+    paths: ShortestPaths<Reindeer, usize>,
+}
+
+impl Inspector {
+    fn show(&self, marks: impl IntoIterator<Item = (TableIdx, char)>) -> String {
+        let mut table = self.day.table.to_chars();
+        for (pos, mark) in marks {
+            if self.day.table[pos] == Tile::Open {
+                table[pos] = mark;
+            }
+        }
+        format!("{:?}", PPCharTable::from(&table))
+    }
+
+    /// The maze with the best seats tinted and one of the best paths painted over them.
+    fn canvas(&self) -> anyhow::Result<Canvas> {
+        let goal = self.paths.goals().first().ok_or(anyhow!("the end can't be reached"))?;
+        let path = self.paths.path_to(goal).unwrap();
+        let canvas = Canvas::new(&self.day.table, |tile| match tile {
+            Tile::Wall => Rgb::GRAY,
+            Tile::Open => Rgb::WHITE,
+            Tile::Start => Rgb::GREEN,
+            Tile::End => Rgb::RED,
+        });
+        Ok(canvas.tint(Day::best_seats(&self.paths), Rgb::YELLOW).overlay(
+            path.iter().map(|reindeer| reindeer.pos).filter(|pos| self.day.table[*pos] == Tile::Open),
+            Rgb::YELLOW,
+        ))
     }
 }
 
+impl Inspect for Inspector {
+    fn load(input: &str) -> anyhow::Result<Self> {
+        let day: Day = input.parse()?;
+        let paths = day.paths();
+        Ok(Inspector { day, paths })
+    }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("show", "show the maze"),
+            ("score", "lowest score to reach the end"),
+            ("path", "show one of the best paths"),
+            ("seats", "show all tiles on any of the best paths"),
+            ("draw", "draw all best seats and one of the best paths in color"),
+            ("save FILE [SCALE]", "write a .png or .ppm picture of the best paths, SCALE pixels per tile"),
+        ]
+    }
+
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+        let goal = self.paths.goals().first().ok_or(anyhow!("the end can't be reached"));
+        match (command, args) {
+            ("show", []) => Ok(self.show([])),
+            ("score", []) => Ok(self.paths.goal_cost().map_or("unreachable".to_string(), |c| c.to_string())),
+            ("path", []) => {
+                let path = self.paths.path_to(goal?).unwrap();
+                Ok(self.show(path.iter().map(|reindeer| (reindeer.pos, char::from(reindeer.dir)))))
+            }
+            ("seats", []) => {
+                let seats: Vec<TableIdx> = Day::best_seats(&self.paths).collect();
+                Ok(format!("{} seats{}", seats.len(), self.show(seats.iter().map(|pos| (*pos, 'O')))))
+            }
+            ("draw", []) => Ok(self.canvas()?.to_ansi()),
+            ("save", [file, scale @ ..]) => {
+                self.canvas()?.save(file, parse_arg(scale, 4)?)?;
+                Ok(format!("saved {}", file))
+            }
+            _ => Err(unknown(command, args)),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
+            ###############\n\
+            #.......#....E#\n\
+            #.#.###.#.###.#\n\
+            #.....#.#...#.#\n\
+            #.###.#####.#.#\n\
+            #.#.#.......#.#\n\
+            #.#.#####.###.#\n\
+            #...........#.#\n\
+            ###.#.#####.#.#\n\
+            #...#.....#.#.#\n\
+            #.#.#.###.#.#.#\n\
+            #.....#...#.#.#\n\
+            #.###.#.#.#.#.#\n\
+            #S..#.....#...#\n\
+            ###############\n\
         "
     }
 
@@ -54,9 +240,86 @@ mod tests {
         example.parse().unwrap()
     }
 
+    #[fixture]
+    fn second_example() -> &'static str {
+        "\
+            #################\n\
+            #...#...#...#..E#\n\
+            #.#.#.#.#.#.#.#.#\n\
+            #.#.#.#...#...#.#\n\
+            #.#.#.#.###.#.#.#\n\
+            #...#.#.#.....#.#\n\
+            #.#.#.#.#.#####.#\n\
+            #.#...#.#.#.....#\n\
+            #.#.#####.#.###.#\n\
+            #.#.#.......#...#\n\
+            #.#.###.#####.###\n\
+            #.#.#...#.....#.#\n\
+            #.#.#.#####.###.#\n\
+            #.#.#.........#.#\n\
+            #.#.#.#########.#\n\
+            #S#.............#\n\
+            #################\n\
+        "
+    }
+
+    #[rstest]
+    #[case(example(), 7036)]
+    #[case(second_example(), 11048)]
+    fn test_part_a(#[case] input: &str, #[case] expected: usize) {
+        let day: Day = input.parse().unwrap();
+        assert_eq!(day.part_a(), Some(expected))
+    }
+
+    #[rstest]
+    #[case(example(), 45)]
+    #[case(second_example(), 64)]
+    fn test_part_b(#[case] input: &str, #[case] expected: usize) {
+        let day: Day = input.parse().unwrap();
+        assert_eq!(day.part_b(), expected)
+    }
+
+    #[rstest]
+    fn inspect_seats(example: &'static str) {
+        let mut inspector = Inspector::load(example).unwrap();
+        assert_eq!(inspector.exec("score", &[]).unwrap(), "7036");
+        assert!(inspector.exec("seats", &[]).unwrap().starts_with("45 seats"));
+        assert!(inspector.exec("path", &[]).unwrap().contains("#^###.#.#.#v#^#"));
+    }
+
+    #[rstest]
+    fn inspect_draw(example: &'static str) {
+        let mut inspector = Inspector::load(example).unwrap();
+        let drawn = inspector.exec("draw", &[]).unwrap();
+        assert_eq!(drawn.lines().count(), 8);
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("paths.png");
+        let file = file.to_str().unwrap();
+        assert_eq!(inspector.exec("save", &[file, "2"]).unwrap(), format!("saved {}", file));
+        let reader = png::Decoder::new(std::fs::File::open(file).unwrap()).read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (30, 30));
+    }
+
     #[rstest]
     fn parse_example_a(example: &'static str, example_parsed: Day) {
         let result: Day = example.parse().unwrap();
         assert_eq!(result, example_parsed)
     }
+
+    #[test]
+    fn inspect_show() {
+        let mut inspector = Inspector::load("####\n#SE#\n####\n").unwrap();
+        assert_eq!(inspector.exec("show", &[]).unwrap(), "\n####\n#SE#\n####\n");
+    }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
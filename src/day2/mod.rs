@@ -67,6 +67,15 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.reports
+            .iter()
+            .map(|r| format!("{}\n", r.0.iter().join(" ")))
+            .collect()
+    }
+}
+
 fn parse_u32(input: &str) -> IResult<&str, u32> {
     map_res(digit1, str::parse)(input)
 }
@@ -138,6 +147,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -209,4 +220,15 @@ mod tests {
     fn test_report_safety(#[case] input: Report, #[case] expected: bool) {
         assert_eq!(input.is_safe(), expected)
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
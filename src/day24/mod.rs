@@ -1,14 +1,22 @@
 use crate::prelude::*;
 
+use crate::repl::{unknown, Inspect};
+
+use crate::graph::export::{interned_labels, Export};
+
 use std::str::FromStr;
 
 use std::collections::HashMap;
 
+use itertools::Itertools;
+
 use lasso::{Spur, RodeoReader, Rodeo};
 
+use petgraph::prelude::*;
+
 use nom::{
     character::complete::{newline, one_of, alphanumeric1},
-    bytes::complete::{tag, take_while},
+    bytes::complete::tag,
     branch::alt,
     combinator::map_res,
     error::Error,
@@ -70,6 +78,36 @@ impl Day {
         usize::from_str_radix(&binstr, 2).unwrap()
     }
 
+    /// The gate network with one node per wire, sorted by name, and an edge from each gate
+    /// input to the wire it drives, labelled with the gate.
+    fn gate_graph(&self) -> DiGraph<Spur, &'static str> {
+        use NodeExpr::*;
+        // Wires that are only read from still get a node, with no gate feeding it.
+        let mut wires: Vec<Spur> = self
+            .device
+            .iter()
+            .flat_map(|(wire, expr)| match *expr {
+                Const(_) => vec![*wire],
+                And(left, right) | Or(left, right) | Xor(left, right) => vec![*wire, left, right],
+            })
+            .unique()
+            .collect();
+        wires.sort_by_key(|spur| self.rodeo.resolve(spur));
+
+        let mut graph = DiGraph::new();
+        let nodes: HashMap<Spur, NodeIndex> = wires.iter().map(|spur| (*spur, graph.add_node(*spur))).collect();
+        for wire in &wires {
+            let (left, right, op) = match self.device.get(wire) {
+                None | Some(Const(_)) => continue,
+                Some(&And(left, right)) => (left, right, "AND"),
+                Some(&Or(left, right)) => (left, right, "OR"),
+                Some(&Xor(left, right)) => (left, right, "XOR"),
+            };
+            graph.add_edge(nodes[&left], nodes[wire], op);
+            graph.add_edge(nodes[&right], nodes[wire], op);
+        }
+        graph
+    }
 }
 
 impl FromStr for Day {
@@ -87,6 +125,32 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        let name = |spur: &Spur| self.rodeo.resolve(spur);
+        let mut consts = Vec::new();
+        let mut gates = Vec::new();
+
+        for (node, expr) in &self.device {
+            use NodeExpr::*;
+            match expr {
+                Const(val) => consts.push(format!("{}: {}\n", name(node), *val as u8)),
+                And(left, right) => gates.push((name(node), name(left), "AND", name(right))),
+                Or(left, right) => gates.push((name(node), name(left), "OR", name(right))),
+                Xor(left, right) => gates.push((name(node), name(left), "XOR", name(right))),
+            }
+        }
+        consts.sort();
+        gates.sort();
+
+        let gates: String = gates
+            .into_iter()
+            .map(|(node, left, op, right)| format!("{} {} {} -> {}\n", left, op, right, node))
+            .collect();
+        format!("{}\n{}", consts.concat(), gates)
+    }
+}
+
 fn parse_day(input: &str) -> IResult<&str, Day> {
     map_res(
         separated_pair(
@@ -110,7 +174,7 @@ fn parse_day(input: &str) -> IResult<&str, Day> {
                     "AND" => NodeExpr::And(leftspur, rightspur),
                     "OR" => NodeExpr::Or(leftspur, rightspur),
                     "XOR" => NodeExpr::Xor(leftspur, rightspur),
-                    _ => unreachable!()
+                    _ => return Err(anyhow::anyhow!("unknown gate {}", gatestr)),
                 };
 
                 device.insert(rodeo.get_or_intern(node), gate);
@@ -170,11 +234,79 @@ enum NodeExpr{
     Xor(Spur, Spur)
 }
 
+pub struct Inspector {
+    day: Day,
+    cache: HashMap<Spur, bool>,
+}
+
+impl Inspector {
+    fn wire(&self, name: &str) -> anyhow::Result<Spur> {
+        self.day
+            .rodeo
+            .get(name)
+            .filter(|spur| self.day.device.contains_key(spur))
+            .ok_or(anyhow::anyhow!("unknown wire {}", name))
+    }
+}
+
+impl Inspect for Inspector {
+    fn load(input: &str) -> anyhow::Result<Self> {
+        Ok(Inspector {
+            day: input.parse()?,
+            cache: HashMap::new(),
+        })
+    }
+
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("wire NAME", "value of the wire"),
+            ("gate NAME", "the gate driving the wire"),
+            ("z", "the number on the z wires"),
+            ("export FILE", "write the gate network as .dot or .graphml"),
+        ]
+    }
+
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+        match (command, args) {
+            ("wire", [name]) => {
+                let spur = self.wire(name)?;
+                Ok((self.day.calc_state(&spur, &mut self.cache) as u8).to_string())
+            }
+            ("gate", [wire]) => {
+                use NodeExpr::*;
+                let name = |spur| self.day.rodeo.resolve(spur);
+                Ok(match self.day.device[&self.wire(wire)?] {
+                    Const(val) => format!("{}", val as u8),
+                    And(left, right) => format!("{} AND {}", name(&left), name(&right)),
+                    Or(left, right) => format!("{} OR {}", name(&left), name(&right)),
+                    Xor(left, right) => format!("{} XOR {}", name(&left), name(&right)),
+                })
+            }
+            ("z", []) => Ok(self.day.part_a().to_string()),
+            ("export", [file]) => {
+                let graph = self.day.gate_graph();
+                let side = |_, spur: &Spur| match self.day.device[spur] {
+                    NodeExpr::Const(_) => Some("inputs".to_string()),
+                    _ => self.day.outputs.contains(spur).then(|| "outputs".to_string()),
+                };
+                Export::new(&graph, interned_labels(&self.day.rodeo))
+                    .edge_labels(|op| op.to_string())
+                    .clusters(side)
+                    .save(file)?;
+                Ok(format!("wrote {} wires and {} gate inputs to {}", graph.node_count(), graph.edge_count(), file))
+            }
+            _ => Err(unknown(command, args)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn small_example() -> &'static str {
         "\
@@ -250,4 +382,50 @@ mod tests {
         let parsed: Day = test.parse().unwrap();
         assert_eq!(parsed.part_a(), cmp)
     }
+
+    #[rstest]
+    #[case("wire z01", "0")]
+    #[case("wire x00", "1")]
+    #[case("gate z02", "x02 OR y02")]
+    #[case("z", "4")]
+    fn inspect_wires(small_example: &'static str, #[case] command: &str, #[case] expected: &str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        let words: Vec<&str> = command.split_whitespace().collect();
+        assert_eq!(inspector.exec(words[0], &words[1..]).unwrap(), expected)
+    }
+
+    #[rstest]
+    fn inspect_export(small_example: &'static str) {
+        let mut inspector = Inspector::load(small_example).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("gates.dot");
+        let file = file.to_str().unwrap();
+        assert_eq!(
+            inspector.exec("export", &[file]).unwrap(),
+            format!("wrote 9 wires and 6 gate inputs to {}", file)
+        );
+        let dot = std::fs::read_to_string(file).unwrap();
+        assert!(dot.starts_with("digraph {\n    subgraph cluster_0 {\n        label=\"inputs\";\n        n0 [label=\"x00\"];"));
+        assert!(dot.contains("        label=\"outputs\";\n        n6 [label=\"z00\"];"));
+        assert!(dot.contains("    n2 -> n8 [label=\"OR\"];"));
+    }
+
+    #[test]
+    fn graph_undriven_wires() {
+        let day: Day = "x00: 1\n\nx00 AND y00 -> z00\n".parse().unwrap();
+        let graph = day.gate_graph();
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+    }
+
+    #[rstest]
+    fn round_trip(small_example: &'static str) {
+        let parsed: Day = small_example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), small_example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(small_example: &'static str) {
+        check_parser::<Day>(small_example)
+    }
 }
@@ -80,6 +80,22 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        let mut ret: String = self
+            .instr
+            .iter()
+            .map(|ins| match ins {
+                Instruction::Mul { left, right } => format!("mul({},{})", left, right),
+                Instruction::Do => "do()".to_string(),
+                Instruction::Dont => "don't()".to_string(),
+            })
+            .collect();
+        ret.push('\n');
+        ret
+    }
+}
+
 fn parse_mul(input: &str) -> IResult<&str, Instruction> {
     map_res(
         delimited(
@@ -129,6 +145,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -186,4 +204,15 @@ mod tests {
     fn test_part_b(example_b_parsed: Day) {
         assert_eq!(example_b_parsed.part_b(), 48)
     }
+
+    #[rstest]
+    fn round_trip(example_parsed: Day) {
+        let reparsed: Day = example_parsed.to_puzzle_string().parse().unwrap();
+        assert_eq!(reparsed, example_parsed)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
@@ -1,21 +1,14 @@
 use crate::prelude::*;
 
 use crate::table::{
-    CharTable,
+    Grid,
     parse_char_table,
-    TableIdx, TableDir,
-    cast_ray,
-    into_idx,
-    into_shape,
-    shift
+    Symmetry, Dir8,
+    search::{find_pattern, find_words, PatternMatch, WordMatch},
 };
 
-use ndarray::Ix2;
-
 use std::str::FromStr;
 
-use itertools::iproduct;
-
 
 impl AoC for Day {
     fn run(input: &str) -> anyhow::Result<AoCResult> {
@@ -30,65 +23,29 @@ impl AoC for Day {
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Day {
-    table: CharTable
+    table: Grid<char>
 }
 
 const XMAS: &str = "XMAS";
-const ALL_DIRS: [TableDir; 8] = [
-    TableDir::new(0, 1),
-    TableDir::new(0, -1),
-    TableDir::new(1, 1),
-    TableDir::new(1, -1),
-    TableDir::new(-1, 1),
-    TableDir::new(-1, -1),
-    TableDir::new(1, 0),
-    TableDir::new(-1, 0),
-];
-
-const X_CORNERS: [TableDir; 4] = [
-    TableDir::new(-1, -1),
-    TableDir::new(1, -1),
-    TableDir::new(1, 1),
-    TableDir::new(-1, 1),
-];
+/// One way to cross two "MAS", with `.` matching anything.
+const X_MAS: &str = "M.S\n.A.\nM.S\n";
 
 impl Day {
-    fn is_xmas(&self, origin: TableIdx, dir: TableDir) -> bool {
-        let mut ray = cast_ray(&self.table, origin, dir).take(4);
-
-        for c in XMAS.chars() {
-            match ray.next() {
-                None => { return false; },
-                Some((_, v)) if *v != c => { return false; },
-                _ => {}
-            }
-
-        }
-
-        true
+    fn xmas(&self) -> Vec<WordMatch> {
+        find_words(&self.table, &[XMAS], Dir8::ALL).expect("XMAS is a word")
     }
 
-    fn is_x_mas(&self, origin: TableIdx) -> bool {
-        if let Some('A') = self.table.get(into_shape(origin)) {
-            if let Some(x) = X_CORNERS.iter().map(|c| self.table.get(into_shape(shift(origin, *c)))).collect::<Option<String>>() {
-                return ["MSSM", "MMSS", "SMMS", "SSMM"].contains(&x.as_str())
-            }
-        }
-        false
+    fn x_mas(&self) -> Vec<PatternMatch> {
+        let pattern: Grid<char> = parse_char_table(X_MAS).expect("pattern is a table").into();
+        find_pattern(&self.table, &pattern, '.', Symmetry::ALL)
     }
 
     fn part_a(&self) -> usize {
-        let (height, width) = self.table.dim();
-        iproduct!(0..width, 0..height).flat_map(|(x, y)| {
-            std::iter::repeat(into_idx(Ix2(x, y))).zip(ALL_DIRS.iter())
-        }).map(|(origin, dir)| self.is_xmas(origin, *dir)).filter(|x| *x).count()
+        self.xmas().len()
     }
 
     fn part_b(&self) -> usize {
-        let (height, width) = self.table.dim();
-        iproduct!(0..width, 0..height).map(|(x, y)| {
-            self.is_x_mas(into_idx(Ix2(x, y)))
-        }).filter(|x| *x).count()
+        self.x_mas().len()
     }
 }
 
@@ -96,12 +53,18 @@ impl FromStr for Day {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Day> {
-        let table = parse_char_table(s)?;
+        let table = parse_char_table(s)?.into();
 
         Ok(Day{ table})
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.table.to_puzzle_string()
+    }
+}
+
 
 
 #[cfg(test)]
@@ -109,6 +72,9 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+    use crate::table::TableIdx;
+
     use ndarray::arr2;
 
     #[fixture]
@@ -141,7 +107,7 @@ mod tests {
                 ['S', 'A', 'X', 'A', 'M', 'A', 'S', 'A', 'A', 'A'],
                 ['M', 'A', 'M', 'M', 'M', 'X', 'M', 'M', 'M', 'M'],
                 ['M', 'X', 'M', 'X', 'A', 'X', 'M', 'A', 'S', 'X']
-            ])
+            ]).into()
         }
     }
 
@@ -152,13 +118,13 @@ mod tests {
     }
 
     #[rstest]
-    #[case(TableIdx::new(4, 0), TableDir::new(1, 1))]
-    #[case(TableIdx::new(5, 0), TableDir::new(1, 0))]
-    #[case(TableIdx::new(9, 9), TableDir::new(0, -1))]
-    #[case(TableIdx::new(9, 9), TableDir::new(-1, -1))]
-    #[case(TableIdx::new(9, 3), TableDir::new(-1, 1))]
-    fn test_xmas_ray(example_parsed: Day, #[case] origin: TableIdx, #[case] dir: TableDir) {
-        assert!(example_parsed.is_xmas(origin, dir))
+    #[case(TableIdx::new(4, 0), Dir8::DownRight)]
+    #[case(TableIdx::new(5, 0), Dir8::Right)]
+    #[case(TableIdx::new(9, 9), Dir8::Up)]
+    #[case(TableIdx::new(9, 9), Dir8::UpLeft)]
+    #[case(TableIdx::new(9, 3), Dir8::DownLeft)]
+    fn test_xmas_ray(example_parsed: Day, #[case] origin: TableIdx, #[case] dir: Dir8) {
+        assert!(example_parsed.xmas().contains(&WordMatch { word: 0, start: origin, dir }))
     }
 
     #[rstest]
@@ -169,11 +135,22 @@ mod tests {
     #[rstest]
     #[case(TableIdx::new(2, 1))]
     fn test_x_mas(example_parsed: Day, #[case] origin: TableIdx) {
-        assert!(example_parsed.is_x_mas(origin))
+        assert!(example_parsed.x_mas().iter().any(|m| m.origin == origin - 1))
     }
 
     #[rstest]
     fn test_part_b(example_parsed: Day) {
         assert_eq!(example_parsed.part_b(), 9)
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
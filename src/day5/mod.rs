@@ -1,7 +1,12 @@
 use crate::prelude::*;
 
+use crate::graph::dag::toposort_subset;
+
+use std::collections::HashMap;
 use std::str::FromStr;
 
+use anyhow::Context;
+
 use nom::{
     bytes::complete::tag,
     character::complete::newline,
@@ -14,13 +19,15 @@ use nom::{
 
 use itertools::Itertools;
 
+use petgraph::prelude::*;
+
 impl AoC for Day {
     fn run(input: &str) -> anyhow::Result<AoCResult> {
         let parsed: Day = input.parse()?;
 
         Ok(AoCResult {
             part_a: Some(parsed.part_a()),
-            part_b: Some(parsed.part_b()),
+            part_b: Some(parsed.part_b()?),
         })
     }
 }
@@ -31,65 +38,92 @@ pub struct Day {
     updates: Vec<Update>,
 }
 
-fn in_correct_order(update: &Update, rules: &[PageOrderRule]) -> bool {
-    ! update.0.iter().combinations(2).map(|x| {
-        PageOrderRule {first: *x[1], second: *x[0]}
-    }).any(|rule| rules.contains(&rule)) 
+/// The rules as a graph with an edge from every page to the pages that must follow it, and a
+/// lookup table from page numbers to their nodes.
+struct Precedence {
+    graph: DiGraph<usize, ()>,
+    nodes: HashMap<usize, NodeIndex>,
 }
 
-fn first_violating_idc(update: &Update, rules: &[PageOrderRule]) -> Option<(usize, usize)> {
-    for rule in update.0.iter().combinations(2).map(|x| {
-        PageOrderRule {first: *x[1], second: *x[0]}
-    }) {
-        if rules.contains(&rule) {
-            let left = update.0.iter().position(|&r| r == rule.first).unwrap();
-            let right = update.0.iter().position(|&r| r == rule.second).unwrap();
-            return Some((left, right));
+impl Precedence {
+    /// Adds a node for every page mentioned in a rule or an update.
+    fn new(day: &Day) -> Self {
+        let mut precedence = Precedence {
+            graph: DiGraph::new(),
+            nodes: HashMap::new(),
+        };
+        for rule in &day.rules {
+            let (first, second) = (precedence.node(rule.first), precedence.node(rule.second));
+            precedence.graph.add_edge(first, second, ());
         }
+        for update in &day.updates {
+            for page in &update.0 {
+                precedence.node(*page);
+            }
+        }
+        precedence
     }
-    None
-}
 
-fn corrected_order(update: &Update, rules: &[PageOrderRule]) -> Update {
-    let relevant_rules: Vec<PageOrderRule> = rules.iter().cloned().filter(|r| {
-        update.0.contains(&r.first) && update.0.contains(&r.second)
-    }).collect();
-    //dbg!(&update, &relevant_rules);
-    let mut retval = update.clone();
-    while let Some((i1, i2)) = first_violating_idc(&retval, &relevant_rules) {
-        retval.0.swap(i1, i2);
+    fn node(&mut self, page: usize) -> NodeIndex {
+        *self.nodes.entry(page).or_insert_with(|| self.graph.add_node(page))
+    }
+
+    fn nodes(&self, update: &Update) -> Vec<NodeIndex> {
+        update.0.iter().map(|page| self.nodes[page]).collect()
+    }
+
+    /// Whether no page of the update comes after one that a rule wants to follow it.
+    fn in_order(&self, update: &Update) -> bool {
+        let nodes = self.nodes(update);
+        let positions: HashMap<NodeIndex, usize> =
+            nodes.iter().enumerate().map(|(idx, node)| (*node, idx)).collect();
+        nodes.iter().enumerate().all(|(idx, node)| {
+            self.graph
+                .neighbors(*node)
+                .all(|next| positions.get(&next).is_none_or(|later| *later > idx))
+        })
+    }
+
+    /// The pages of the update sorted by the rules between them.
+    fn corrected(&self, update: &Update) -> anyhow::Result<Update> {
+        let order = toposort_subset(&self.graph, &self.nodes(update)).with_context(|| {
+            format!("rules for update {} contradict each other", update.0.iter().join(","))
+        })?;
+        Ok(Update(order.into_iter().map(|node| self.graph[node]).collect()))
     }
-    retval
 }
 
 impl Day {
     #[cfg(test)]
     fn correctly_ordered(&self) -> impl Iterator<Item = bool> + '_ {
-        self.updates
-            .iter()
-            .map(|u| in_correct_order(u, &self.rules))
+        let precedence = Precedence::new(self);
+        self.updates.iter().map(move |u| precedence.in_order(u))
     }
 
-    fn correct(&self) -> impl Iterator<Item=Update> + '_ {
-        self.updates.iter().filter_map(|u| {
-            if ! in_correct_order(u, &self.rules) {
-                return Some(corrected_order(u, &self.rules));
-            }
-            None
-        })
+    fn correct(&self) -> impl Iterator<Item = anyhow::Result<Update>> + '_ {
+        let precedence = Precedence::new(self);
+        self.updates
+            .iter()
+            .filter_map(move |u| {
+                if precedence.in_order(u) {
+                    None
+                } else {
+                    Some(precedence.corrected(u))
+                }
+            })
     }
 
     fn part_a(&self) -> usize {
-        self.updates.iter().filter_map(|u| {
-            if in_correct_order(u, &self.rules) {
-                return Some(u.0[u.0.len()/2]);
-            }
-            None
-        }).sum()
+        let precedence = Precedence::new(self);
+        self.updates
+            .iter()
+            .filter(|u| precedence.in_order(u))
+            .map(|u| u.0[u.0.len() / 2])
+            .sum()
     }
 
-    fn part_b(&self) -> usize {
-        self.correct().map(|u| { u.0[u.0.len()/2] }).sum()
+    fn part_b(&self) -> anyhow::Result<usize> {
+        self.correct().map(|u| u.map(|u| u.0[u.0.len() / 2])).sum()
     }
 }
 
@@ -113,6 +147,22 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        let rules: String = self
+            .rules
+            .iter()
+            .map(|r| format!("{}|{}\n", r.first, r.second))
+            .collect();
+        let updates: String = self
+            .updates
+            .iter()
+            .map(|u| format!("{}\n", u.0.iter().join(",")))
+            .collect();
+        format!("{}\n{}", rules, updates)
+    }
+}
+
 fn parse_day(input: &str) -> IResult<&str, Day> {
     map_res(
         separated_pair(
@@ -143,7 +193,12 @@ struct Update(Vec<usize>);
 fn parse_update(input: &str) -> IResult<&str, Update> {
     map_res(
         separated_list1(tag(","), parse_usize),
-        |v| -> anyhow::Result<Update> { Ok(Update(v)) },
+        |v| -> anyhow::Result<Update> {
+            if !v.iter().all_unique() {
+                return Err(anyhow::anyhow!("update {} repeats a page", v.iter().join(",")));
+            }
+            Ok(Update(v))
+        },
     )(input)
 }
 
@@ -152,6 +207,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -307,7 +364,7 @@ mod tests {
 
     #[rstest]
     fn test_correct(example_parsed: Day) {
-        let corrected: Vec<Update> = example_parsed.correct().collect();
+        let corrected: Vec<Update> = example_parsed.correct().collect::<anyhow::Result<_>>().unwrap();
         assert_eq!(corrected, [
             Update([97,75,47,61,53].into()),
             Update([61,29,13].into()),
@@ -317,6 +374,52 @@ mod tests {
     }
     #[rstest]
     fn test_part_b(example_parsed: Day) {
-        assert_eq!(example_parsed.part_b(), 123)
+        assert_eq!(example_parsed.part_b().unwrap(), 123)
+    }
+
+    #[test]
+    fn cyclic_rules() {
+        // The rules contradict each other only when all three pages are in one update.
+        let day: Day = "1|2\n2|3\n3|1\n\n2,1\n3,2\n1,3,2\n".parse().unwrap();
+        let corrected: Vec<String> = day
+            .correct()
+            .map(|u| match u {
+                Ok(u) => u.0.iter().join(","),
+                Err(err) => format!("{:#}", err),
+            })
+            .collect();
+        assert_eq!(
+            corrected,
+            [
+                "1,2",
+                "2,3",
+                "rules for update 1,3,2 contradict each other: graph has a cycle through nodes 1, 2, 0"
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_repeated_pages() {
+        assert!("2|1\n1|3\n\n3,1,2,1\n".parse::<Day>().is_err());
+    }
+
+    #[test]
+    fn huge_page_numbers() {
+        let day: Day = "1|99999999999\n99999999999|18446744073709551615\n\n18446744073709551615,1,99999999999\n"
+            .parse()
+            .unwrap();
+        assert_eq!(day.part_a(), 0);
+        assert_eq!(day.part_b().unwrap(), 99999999999);
+    }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
     }
 }
@@ -1,12 +1,12 @@
 use crate::prelude::*;
 
+use crate::repl::{parse_arg, unknown, Inspect};
+
 use rayon::prelude::*;
 
 use anyhow::anyhow;
 
-use crate::table::{into_idx, into_shape, parse_char_table, shift, CharTable, TableDir, TableIdx};
-
-use ndarray::Ix2;
+use crate::table::{cast_ray, cast_segment, parse_grid, Canvas, Dir4, Grid, Rgb, TableIdx};
 
 use itertools::Itertools;
 
@@ -14,6 +14,8 @@ use std::str::FromStr;
 
 use std::collections::HashSet;
 
+use std::iter;
+
 impl AoC for Day {
     fn run(input: &str) -> anyhow::Result<AoCResult> {
         let parsed: Day = input.parse()?;
@@ -25,41 +27,86 @@ impl AoC for Day {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Tile {
+    Open,
+    Obstruction,
+    Guard,
+}
+
+impl TryFrom<char> for Tile {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, char> {
+        match c {
+            '.' => Ok(Tile::Open),
+            '#' => Ok(Tile::Obstruction),
+            '^' => Ok(Tile::Guard),
+            c => Err(c),
+        }
+    }
+}
+
+impl From<Tile> for char {
+    fn from(tile: Tile) -> char {
+        match tile {
+            Tile::Open => '.',
+            Tile::Obstruction => '#',
+            Tile::Guard => '^',
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Day {
-    table: CharTable,
+    table: Grid<Tile>,
     start: TableIdx,
 }
 
 impl Day {
     fn drop_boulder(&self, pos: TableIdx) -> Option<Self> {
-        let shape = into_shape(pos);
-        if let Some('.') = self.table.get(shape) {
+        if let Some(Tile::Open) = self.table.get(pos) {
             let mut ret = self.clone();
-            ret.table[shape] = '#';
+            ret.table[pos] = Tile::Obstruction;
             return Some(ret);
         }
         None
     }
 
-    fn walk(&self) -> GuardPath {
-        GuardPath {
-            table: &self.table,
-            guard: Guard {
-                pos: self.start,
-                dir: TableDir::new(0, -1),
-            },
-        }
+    /// The guard at the start and after walking up to each obstruction and turning. The last
+    /// one stands at the edge of the map, about to leave it.
+    fn turns(&self) -> impl Iterator<Item = Guard> + '_ {
+        let start = Guard {
+            pos: self.start,
+            dir: Dir4::Up,
+        };
+        iter::successors(Some(start), |guard| {
+            guard.look(&self.table)?;
+            let (pos, _) = cast_ray(&self.table, guard.pos.as_(), guard.dir.to_vec())
+                .stop_before(|tile| *tile == Tile::Obstruction)
+                .last()?;
+            let mut next = Guard { pos, ..*guard };
+            if next.look(&self.table).is_some() {
+                next.turn();
+            }
+            Some(next)
+        })
     }
 
+    /// Every step of the guard, filled in along the straight legs between turns.
+    fn walk(&self) -> impl Iterator<Item = Guard> + '_ {
+        self.turns().take(1).chain(self.turns().tuple_windows().flat_map(|(from, to)| {
+            cast_segment(&self.table, from.pos.as_(), to.pos.as_())
+                .skip(1)
+                .map(move |(pos, _)| Guard { pos, dir: from.dir })
+        }))
+    }
+
+    /// Whether the guard walks in circles. Only the spots where the guard turns are looked
+    /// at, walking straight up to the next obstruction in one go.
     fn is_stuck(&self) -> bool {
-        let mut trace: HashSet<Guard> = HashSet::new();
-        for guard in self.walk() {
-            if ! trace.insert(guard) {
-                return true;
-            }
-        }
-        false
+        let mut turns: HashSet<Guard> = HashSet::new();
+        self.turns().any(|guard| !turns.insert(guard))
     }
 
     fn unique_guard_pos(&self) -> impl Iterator<Item = TableIdx> + '_ {
@@ -90,78 +137,78 @@ impl FromStr for Day {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> anyhow::Result<Day> {
-        let table = parse_char_table(s)?;
+        let table: Grid<Tile> = parse_grid(s)?;
 
-        let ((row, col), _) = table
-            .indexed_iter()
-            .find_or_last(|((_row, _col), c)| *c == &'^')
+        let start = table
+            .find(|tile| *tile == Tile::Guard)
             .ok_or(anyhow!("couldn't find start position"))?;
 
-        Ok(Day {
-            table,
-            start: into_idx(Ix2(row, col)),
-        })
+        Ok(Day { table, start })
+    }
+}
+
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.table.to_puzzle_string()
     }
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
 struct Guard {
     pos: TableIdx,
-    dir: TableDir,
+    dir: Dir4,
 }
 
 impl Guard {
-    fn turned(&self) -> TableDir {
-        self.dir.as_().rotated_z(std::f32::consts::PI * 0.5).as_()
-    }
-
     fn turn(&mut self) {
-        self.dir = self.turned();
-    }
-
-    fn step(&mut self) {
-        self.pos = self.look()
+        self.dir = self.dir.turn_right();
     }
 
-    fn look(&self) -> TableIdx {
-        shift(self.pos, self.dir)
+    fn look(&self, table: &Grid<Tile>) -> Option<TableIdx> {
+        table.shift(self.pos, self.dir.to_vec())
     }
 }
 
-struct GuardPath<'a> {
-    table: &'a CharTable,
-    guard: Guard,
+pub struct Inspector {
+    day: Day,
 }
 
-impl GuardPath<'_> {
-    fn get(&self, pos: TableIdx) -> Option<&char> {
-        self.table.get(into_shape(pos))
+impl Inspector {
+    /// The lab with the guard's path painted over it.
+    fn canvas(&self) -> Canvas {
+        Canvas::new(&self.day.table, |tile| match tile {
+            Tile::Open => Rgb::WHITE,
+            Tile::Obstruction => Rgb::BLACK,
+            Tile::Guard => Rgb::GREEN,
+        })
+        .overlay(self.day.unique_guard_pos(), Rgb::RED)
+        .overlay([self.day.start], Rgb::GREEN)
     }
+}
 
-    fn get_guard_pos(&self) -> Option<&char> {
-        self.get(self.guard.pos)
+impl Inspect for Inspector {
+    fn load(input: &str) -> anyhow::Result<Self> {
+        Ok(Inspector { day: input.parse()? })
     }
 
-    fn blocked(&self) -> bool {
-        self.get(self.guard.look()) == Some(&'#')
+    fn commands(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("visited", "number of positions the guard visits"),
+            ("path", "draw the guard's path in color"),
+            ("save FILE [SCALE]", "write a .png or .ppm picture of the path, SCALE pixels per tile"),
+        ]
     }
-}
-
-impl<'a> Iterator for GuardPath<'a> {
-    type Item = Guard;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.get_guard_pos()?;
-
-        let retval = self.guard;
-
-        while self.blocked() {
-            self.guard.turn();
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+        match (command, args) {
+            ("visited", []) => Ok(self.day.part_a().to_string()),
+            ("path", []) => Ok(self.canvas().to_ansi()),
+            ("save", [file, scale @ ..]) => {
+                self.canvas().save(file, parse_arg(scale, 4)?)?;
+                Ok(format!("saved {}", file))
+            }
+            _ => Err(unknown(command, args)),
         }
-
-        self.guard.step();
-
-        Some(retval)
     }
 }
 
@@ -170,6 +217,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     use ndarray::arr2;
 
     #[fixture]
@@ -191,7 +240,7 @@ mod tests {
     #[fixture]
     fn example_parsed() -> Day {
         Day {
-            table: arr2(&[
+            table: Grid::from(arr2(&[
                 ['.', '.', '.', '.', '#', '.', '.', '.', '.', '.'],
                 ['.', '.', '.', '.', '.', '.', '.', '.', '.', '#'],
                 ['.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
@@ -202,7 +251,8 @@ mod tests {
                 ['.', '.', '.', '.', '.', '.', '.', '.', '#', '.'],
                 ['#', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
                 ['.', '.', '.', '.', '.', '.', '#', '.', '.', '.'],
-            ]),
+            ]))
+            .map(|c| Tile::try_from(*c).unwrap()),
             start: TableIdx::new(4, 6),
         }
     }
@@ -221,31 +271,31 @@ mod tests {
             vec![
                 Guard {
                     pos: TableIdx::new(4, 6),
-                    dir: TableDir::new(0, -1)
+                    dir: Dir4::Up
                 },
                 Guard {
                     pos: TableIdx::new(4, 5),
-                    dir: TableDir::new(0, -1)
+                    dir: Dir4::Up
                 },
                 Guard {
                     pos: TableIdx::new(4, 4),
-                    dir: TableDir::new(0, -1)
+                    dir: Dir4::Up
                 },
                 Guard {
                     pos: TableIdx::new(4, 3),
-                    dir: TableDir::new(0, -1)
+                    dir: Dir4::Up
                 },
                 Guard {
                     pos: TableIdx::new(4, 2),
-                    dir: TableDir::new(0, -1)
+                    dir: Dir4::Up
                 },
                 Guard {
                     pos: TableIdx::new(4, 1),
-                    dir: TableDir::new(0, -1)
+                    dir: Dir4::Up
                 },
                 Guard {
                     pos: TableIdx::new(5, 1),
-                    dir: TableDir::new(1, 0)
+                    dir: Dir4::Right
                 },
             ]
         )
@@ -277,4 +327,36 @@ mod tests {
     fn test_part_b(example_parsed: Day) {
         assert_eq!(example_parsed.part_b(), 6)
     }
+
+    #[rstest]
+    fn inspect_path(example: &'static str) {
+        let mut inspector = Inspector::load(example).unwrap();
+        assert_eq!(inspector.exec("visited", &[]).unwrap(), "41");
+        let drawn = inspector.exec("path", &[]).unwrap();
+        assert_eq!(drawn.lines().count(), 5);
+        assert_eq!(drawn.matches("38;2;220;50;47m").count() + drawn.matches("48;2;220;50;47m").count(), 40);
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("path.ppm");
+        let file = file.to_str().unwrap();
+        assert_eq!(inspector.exec("save", &[file, "1"]).unwrap(), format!("saved {}", file));
+        assert_eq!(std::fs::read(file).unwrap().len(), "P6\n10 10\n255\n".len() + 300);
+    }
+
+    #[rstest]
+    fn reject_unknown_tile() {
+        let err = "..#\n.X^\n".parse::<Day>().unwrap_err();
+        assert_eq!(err.to_string(), "unknown symbol 'X' at row 2, column 2")
+    }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
@@ -39,7 +39,7 @@ impl Day {
         self.evaluates_with(&["*", "+", "||"]).sum()
     }
 
-    fn evaluates_with<'a>(&'a self, ops: &'a [&'static str]) -> impl Iterator<Item=usize> + '_ {
+    fn evaluates_with<'a>(&'a self, ops: &'a [&'static str]) -> impl Iterator<Item=usize> + 'a {
         self.entries.iter().filter_map(|e| {
             if e.evaluates(ops) {
                 Some(e.left)
@@ -70,6 +70,15 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.entries
+            .iter()
+            .map(|e| format!("{}: {}\n", e.left, e.right.iter().join(" ")))
+            .collect()
+    }
+}
+
 fn parse_day(input: &str) -> IResult<&str, Day> {
     map_res(
         terminated(separated_list1(newline, parse_entry), newline),
@@ -127,6 +136,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -201,4 +212,15 @@ mod tests {
     fn test_part_b(example_parsed: Day) {
         assert_eq!(example_parsed.part_b(), 11387)
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
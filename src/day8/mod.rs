@@ -4,10 +4,10 @@ use std::str::FromStr;
 
 use std::collections::HashMap;
 
-use itertools::{Itertools, iproduct};
+use itertools::Itertools;
 
 use crate::table::{
-    into_idx, cast_ray, parse_char_table, CharTable, TableDir, TableIdx,
+    into_idx, cast_ray, parse_char_table, shift_within, CharTable, TableIdx,
 };
 
 use ndarray::Ix2;
@@ -30,36 +30,26 @@ pub struct Day {
 }
 
 impl Day {
-    fn is_antinode_for(&self, pos: TableIdx) -> impl Iterator<Item=char> + '_ {
-        self.antennas.iter().filter_map(move |(a, v)| {
-            for x in v.iter().combinations(2) {
-                let mut dists : Vec<TableDir> = vec![x[0].as_() - pos.as_(), x[1].as_() - pos.as_()];
-                dists.sort_by_key(|a| a.magnitude_squared());
-
-                //dbg!(&dists);
-
-                if  2*dists[0].as_() == dists[1].as_() {
-                    //dbg!(a, &pos);
-                    return Some(*a);
-                }
-            }
-            None
+    /// Every antinode on the map with the frequency of the antennas causing it. Each pair of
+    /// antennas has one antinode beyond either of them.
+    fn antinodes(&self) -> impl Iterator<Item = (char, TableIdx)> + '_ {
+        self.antennas.iter().flat_map(move |(frequency, antennas)| {
+            antennas.iter().permutations(2).filter_map(move |pair| {
+                let antinode = shift_within(*pair[1], pair[1].as_() - pair[0].as_(), self.table.dim())?;
+                Some((*frequency, antinode))
+            })
         })
     }
 
     fn part_a(&self) -> usize {
-        let (height, width) = self.table.dim();
-        iproduct!(0..width, 0..height).filter(|(x, y)| {
-            let idx = into_idx(Ix2(*x, *y));
-            self.is_antinode_for(idx).next().is_some()
-        }).count()
+        self.antinodes().map(|(_, antinode)| antinode).unique().count()
     }
 
     fn part_b(&self) -> usize {
         self.antennas.values().flat_map(|v| {
             v.iter().combinations(2).flat_map(|dvec| {
                 let dir = dvec[1].as_() - dvec[0].as_();
-                cast_ray(&self.table, *dvec[0], dir).chain(cast_ray(&self.table, *dvec[1], -dir)).map(|(pos, _v)| pos)
+                cast_ray(&self.table, dvec[0].as_(), dir).both_ways().map(|(pos, _v)| pos)
             })
         }).unique().count()
     }
@@ -88,11 +78,19 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        self.table.to_puzzle_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     use std::collections::HashSet;
 
     use ndarray::arr2;
@@ -166,7 +164,11 @@ mod tests {
     #[case(TableIdx { x: 6, y: 0 }, vec!['0'])]
     #[case(TableIdx { x: 0, y: 0 }, vec![])]
     fn test_antinodes(example_parsed: Day, #[case] pos: TableIdx, #[case] expected: Vec<char>) {
-        let antinodes: HashSet<char> = example_parsed.is_antinode_for(pos).collect();
+        let antinodes: HashSet<char> = example_parsed
+            .antinodes()
+            .filter(|(_, antinode)| *antinode == pos)
+            .map(|(frequency, _)| frequency)
+            .collect();
         assert_eq!(
             antinodes,
             expected.into_iter().collect()
@@ -181,4 +183,15 @@ mod tests {
     fn test_part_b(example_parsed: Day) {
         assert_eq!(example_parsed.part_b(), 34)
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
@@ -45,6 +45,18 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        let mut ret: String = self
+            .values
+            .iter()
+            .map(|v| char::from_digit(*v, 10).expect("disk map digits are 0-9"))
+            .collect();
+        ret.push('\n');
+        ret
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct FileSystem {
     entries: Vec<Entry>,
@@ -256,6 +268,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -362,4 +376,15 @@ mod tests {
             "00992111777.44.333....5555.6666.....8888.."
         )
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }
@@ -0,0 +1,136 @@
+use std::fmt::Debug;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::str::FromStr;
+
+use crate::prelude::ToPuzzleString;
+
+use rand_core::{RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+
+const ROUNDS: usize = 1000;
+
+const MAX_MUTATIONS: u64 = 4;
+
+const NOISE: [char; 12] = ['\n', ' ', '-', '+', '0', '9', 'x', '#', ':', ',', '.', 'é'];
+
+struct Mutator {
+    rng: Pcg64,
+    alphabet: Vec<char>,
+}
+
+impl Mutator {
+    fn new(seed: &str) -> Self {
+        let mut alphabet: Vec<char> = seed.chars().chain(NOISE).collect();
+        alphabet.sort();
+        alphabet.dedup();
+        Self {
+            rng: Pcg64::seed_from_u64(0xa0c_2024),
+            alphabet,
+        }
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.rng.next_u64() % n.max(1) as u64) as usize
+    }
+
+    fn any_char(&mut self) -> char {
+        let idx = self.below(self.alphabet.len());
+        self.alphabet[idx]
+    }
+
+    fn mutate_chars(&mut self, chars: &mut Vec<char>) {
+        let pos = self.below(chars.len() + 1);
+        match self.below(4) {
+            0 if pos < chars.len() => {
+                chars.remove(pos);
+            }
+            1 if pos < chars.len() => chars[pos] = self.any_char(),
+            2 => chars.truncate(pos),
+            _ => {
+                let c = self.any_char();
+                chars.insert(pos, c);
+            }
+        }
+    }
+
+    fn mutate_lines(&mut self, lines: &mut Vec<String>) {
+        let pos = self.below(lines.len());
+        match self.below(3) {
+            0 if pos < lines.len() => {
+                lines.remove(pos);
+            }
+            1 if pos < lines.len() => {
+                let line = lines[pos].clone();
+                lines.insert(pos, line);
+            }
+            _ => {
+                let other = self.below(lines.len());
+                if pos < lines.len() && other < lines.len() {
+                    lines.swap(pos, other);
+                }
+            }
+        }
+    }
+
+    fn mutate(&mut self, seed: &str) -> String {
+        let mut out = seed.to_string();
+        for _ in 0..=self.rng.next_u64() % MAX_MUTATIONS {
+            if self.below(2) == 0 {
+                let mut chars: Vec<char> = out.chars().collect();
+                self.mutate_chars(&mut chars);
+                out = chars.into_iter().collect();
+            } else {
+                let mut lines: Vec<String> = out.lines().map(str::to_string).collect();
+                self.mutate_lines(&mut lines);
+                out = lines.join("\n");
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// Feeds the seed input and a deterministic set of mutations of it into the parser of `T`
+/// and fails if parsing panics for any of them.
+///
+/// Every successfully parsed value has to round-trip: its puzzle string parses again and
+/// serializes to the same text.
+pub fn check_parser<T>(seed: &str)
+where
+    T: FromStr + ToPuzzleString,
+    T::Err: Debug,
+{
+    let mut mutator = Mutator::new(seed);
+
+    let inputs = ["".to_string(), "\n".to_string(), seed.to_string()]
+        .into_iter()
+        .chain((0..ROUNDS).map(|_| mutator.mutate(seed)));
+
+    for input in inputs {
+        match catch_unwind(AssertUnwindSafe(|| input.parse::<T>())) {
+            Err(_) => panic!("parser panicked on input {:?}", input),
+            Ok(Err(_)) => {}
+            Ok(Ok(parsed)) => check_round_trip(&parsed, &input),
+        }
+    }
+}
+
+fn check_round_trip<T>(parsed: &T, input: &str)
+where
+    T: FromStr + ToPuzzleString,
+    T::Err: Debug,
+{
+    let serialized = parsed.to_puzzle_string();
+    let reparsed: T = serialized.parse().unwrap_or_else(|e| {
+        panic!(
+            "serialized form {:?} of input {:?} does not parse: {:?}",
+            serialized, input, e
+        )
+    });
+    assert_eq!(
+        reparsed.to_puzzle_string(),
+        serialized,
+        "round-trip of input {:?} is not stable",
+        input
+    );
+}
@@ -0,0 +1,237 @@
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::anyhow;
+
+use fixedbitset::FixedBitSet;
+
+use itertools::Itertools;
+
+use petgraph::prelude::*;
+
+/// The `subset` nodes in topological order of the edges between them, ignoring all other
+/// nodes. Repeated nodes are kept once, and nodes that don't depend on each other come in no
+/// particular order. Fails with the members of a cycle if there is one, and takes time linear
+/// in the subset and the edges touching it.
+pub fn toposort_subset<N, E>(
+    graph: &DiGraph<N, E>,
+    subset: &[NodeIndex],
+) -> anyhow::Result<Vec<NodeIndex>> {
+    let subset: Vec<NodeIndex> = subset.iter().copied().unique().collect();
+    let mut incoming: HashMap<NodeIndex, usize> = subset.iter().map(|node| (*node, 0)).collect();
+    for node in &subset {
+        for next in graph.neighbors(*node) {
+            if let Some(count) = incoming.get_mut(&next) {
+                *count += 1;
+            }
+        }
+    }
+
+    let mut ready: VecDeque<NodeIndex> = subset
+        .iter()
+        .filter(|node| incoming[node] == 0)
+        .copied()
+        .collect();
+    let mut order = Vec::with_capacity(subset.len());
+    while let Some(node) = ready.pop_front() {
+        order.push(node);
+        for next in graph.neighbors(node) {
+            if let Some(count) = incoming.get_mut(&next) {
+                *count -= 1;
+                if *count == 0 {
+                    ready.push_back(next);
+                }
+            }
+        }
+    }
+    if order.len() == subset.len() {
+        return Ok(order);
+    }
+
+    // Every node left over still has a predecessor left over, so walking backwards from any
+    // of them must run into a cycle.
+    let left = |node: &NodeIndex| incoming.get(node).is_some_and(|count| *count > 0);
+    let mut walk: Vec<NodeIndex> = subset.iter().copied().filter(left).take(1).collect();
+    let mut positions: HashMap<NodeIndex, usize> = walk.iter().map(|node| (*node, 0)).collect();
+    let start = loop {
+        let Some(prev) = walk
+            .last()
+            .and_then(|node| graph.neighbors_directed(*node, Incoming).find(left))
+        else {
+            return Err(anyhow!("graph has a cycle, but no way back along it was found"));
+        };
+        if let Some(start) = positions.get(&prev) {
+            break *start;
+        }
+        positions.insert(prev, walk.len());
+        walk.push(prev);
+    };
+    let members = walk[start..]
+        .iter()
+        .rev()
+        .map(|node| node.index())
+        .join(", ");
+    Err(anyhow!("graph has a cycle through nodes {}", members))
+}
+
+/// The nodes in topological order, or an error naming the nodes on a cycle.
+fn topological_order<N, E>(graph: &DiGraph<N, E>) -> anyhow::Result<Vec<NodeIndex>> {
+    let nodes: Vec<NodeIndex> = graph.node_indices().collect();
+    toposort_subset(graph, &nodes)
+}
+
+/// Number of distinct paths from every node to any of `targets`, indexed by node. A target
+/// counts as a path of its own, and paths may go on through a target to another one.
+/// Takes one pass over the graph in reverse topological order.
+pub fn path_counts<N, E>(
+    graph: &DiGraph<N, E>,
+    targets: &[NodeIndex],
+) -> anyhow::Result<Vec<usize>> {
+    let mut counts = vec![0; graph.node_count()];
+    for target in targets {
+        counts[target.index()] = 1;
+    }
+    for node in topological_order(graph)?.into_iter().rev() {
+        let onwards: usize = graph.neighbors(node).map(|next| counts[next.index()]).sum();
+        counts[node.index()] += onwards;
+    }
+    Ok(counts)
+}
+
+/// For every node the set of `targets` reachable from it, as bits at the targets' positions
+/// in the slice. Passing all nodes as targets gives the reachability between all pairs.
+pub fn reachable_sets<N, E>(
+    graph: &DiGraph<N, E>,
+    targets: &[NodeIndex],
+) -> anyhow::Result<Vec<FixedBitSet>> {
+    let mut sets = vec![FixedBitSet::with_capacity(targets.len()); graph.node_count()];
+    for (bit, target) in targets.iter().enumerate() {
+        sets[target.index()].insert(bit);
+    }
+    for node in topological_order(graph)?.into_iter().rev() {
+        let mut set = std::mem::take(&mut sets[node.index()]);
+        for next in graph.neighbors(node) {
+            set.union_with(&sets[next.index()]);
+        }
+        sets[node.index()] = set;
+    }
+    Ok(sets)
+}
+
+/// Number of distinct `targets` reachable from every node, indexed by node.
+pub fn reachable_counts<N, E>(
+    graph: &DiGraph<N, E>,
+    targets: &[NodeIndex],
+) -> anyhow::Result<Vec<usize>> {
+    Ok(reachable_sets(graph, targets)?
+        .iter()
+        .map(|set| set.count_ones(..))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    /// Two diamonds in a row, a -> {b, c} -> d -> {e, f} -> g, and a lone node h.
+    #[fixture]
+    fn diamonds() -> DiGraph<char, ()> {
+        let mut graph = DiGraph::new();
+        let nodes: Vec<NodeIndex> = "abcdefgh".chars().map(|c| graph.add_node(c)).collect();
+        for (from, to) in [
+            (0, 1),
+            (0, 2),
+            (1, 3),
+            (2, 3),
+            (3, 4),
+            (3, 5),
+            (4, 6),
+            (5, 6),
+        ] {
+            graph.add_edge(nodes[from], nodes[to], ());
+        }
+        graph
+    }
+
+    fn node(graph: &DiGraph<char, ()>, c: char) -> NodeIndex {
+        graph.node_indices().find(|n| graph[*n] == c).unwrap()
+    }
+
+    #[rstest]
+    fn count_paths(diamonds: DiGraph<char, ()>) {
+        let g = node(&diamonds, 'g');
+        let counts = path_counts(&diamonds, &[g]).unwrap();
+        assert_eq!(counts, [4, 2, 2, 2, 1, 1, 1, 0]);
+
+        let d = node(&diamonds, 'd');
+        let counts = path_counts(&diamonds, &[d, g]).unwrap();
+        assert_eq!(counts[node(&diamonds, 'a').index()], 2 + 4);
+    }
+
+    #[rstest]
+    fn all_pairs(diamonds: DiGraph<char, ()>) {
+        let nodes: Vec<NodeIndex> = diamonds.node_indices().collect();
+        let sets = reachable_sets(&diamonds, &nodes).unwrap();
+        let reached = |from: char| -> String {
+            sets[node(&diamonds, from).index()]
+                .ones()
+                .map(|bit| diamonds[nodes[bit]])
+                .collect()
+        };
+        assert_eq!(reached('a'), "abcdefg");
+        assert_eq!(reached('e'), "eg");
+        assert_eq!(reached('h'), "h");
+    }
+
+    #[rstest]
+    fn count_reachable(diamonds: DiGraph<char, ()>) {
+        let targets = [
+            node(&diamonds, 'b'),
+            node(&diamonds, 'e'),
+            node(&diamonds, 'g'),
+        ];
+        assert_eq!(
+            reachable_counts(&diamonds, &targets).unwrap(),
+            [3, 3, 2, 2, 2, 1, 1, 0]
+        );
+    }
+
+    #[rstest]
+    fn reject_cycles(mut diamonds: DiGraph<char, ()>) {
+        let (g, a) = (node(&diamonds, 'g'), node(&diamonds, 'a'));
+        diamonds.add_edge(g, a, ());
+        assert!(path_counts(&diamonds, &[g])
+            .unwrap_err()
+            .to_string()
+            .starts_with("graph has a cycle"));
+        assert!(reachable_counts(&diamonds, &[g]).is_err());
+    }
+
+    #[rstest]
+    fn sort_subset(diamonds: DiGraph<char, ()>) {
+        let subset: Vec<NodeIndex> = "hgcea".chars().map(|c| node(&diamonds, c)).collect();
+        let sorted: String = toposort_subset(&diamonds, &subset)
+            .unwrap()
+            .into_iter()
+            .map(|n| diamonds[n])
+            .collect();
+        // Only the edges from a to c and from e to g stay within the subset.
+        assert_eq!(sorted, "heagc");
+    }
+
+    #[rstest]
+    fn report_cycle_members(mut diamonds: DiGraph<char, ()>) {
+        let [a, d, e, g] = ['a', 'd', 'e', 'g'].map(|c| node(&diamonds, c));
+        diamonds.add_edge(g, d, ());
+        let err = toposort_subset(&diamonds, &[a, d, e, g]).unwrap_err();
+        assert_eq!(err.to_string(), "graph has a cycle through nodes 4, 6, 3");
+        // Leaving out e breaks the cycle.
+        assert_eq!(toposort_subset(&diamonds, &[g, d, a]).unwrap(), [g, a, d]);
+    }
+
+    #[rstest]
+    fn sort_repeated_nodes(diamonds: DiGraph<char, ()>) {
+        let [a, c, e] = ['a', 'c', 'e'].map(|c| node(&diamonds, c));
+        assert_eq!(toposort_subset(&diamonds, &[c, a, c, e, a]).unwrap(), [a, e, c]);
+    }
+}
@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use lasso::{Resolver, Spur};
+
+use petgraph::{prelude::*, unionfind::UnionFind, EdgeType};
+
+use super::PositionMap;
+
+type NodeText<'a, N> = Box<dyn Fn(NodeIndex, &N) -> String + 'a>;
+type NodeCluster<'a, N> = Box<dyn Fn(NodeIndex, &N) -> Option<String> + 'a>;
+type EdgeText<'a, E> = Box<dyn Fn(&E) -> String + 'a>;
+
+/// Writes a graph in formats other tools can draw, Graphviz DOT and GraphML.
+pub struct Export<'a, N, E, Ty> {
+    graph: &'a Graph<N, E, Ty>,
+    node_label: NodeText<'a, N>,
+    edge_label: Option<EdgeText<'a, E>>,
+    cluster: Option<NodeCluster<'a, N>>,
+}
+
+/// Labels nodes with the grid position they stand for, as `x,y`.
+pub fn position_labels<N, M: PositionMap>(nodemap: &M) -> impl Fn(NodeIndex, &N) -> String + '_ {
+    move |node, _| match nodemap.position(node) {
+        Some(pos) => format!("{},{}", pos.x, pos.y),
+        None => format!("#{}", node.index()),
+    }
+}
+
+/// Labels nodes weighted with interned strings by the strings themselves.
+pub fn interned_labels<R: Resolver>(rodeo: &R) -> impl Fn(NodeIndex, &Spur) -> String + '_ {
+    move |_, spur| rodeo.resolve(spur).to_string()
+}
+
+/// The connected component of every node, indexed by node. Edge directions are ignored and
+/// components are numbered by their first node.
+pub fn components<N, E, Ty: EdgeType>(graph: &Graph<N, E, Ty>) -> Vec<usize> {
+    let mut sets = UnionFind::new(graph.node_count());
+    for edge in graph.edge_references() {
+        sets.union(edge.source().index(), edge.target().index());
+    }
+    let roots = sets.into_labeling();
+    let mut numbers = HashMap::new();
+    roots
+        .iter()
+        .map(|root| {
+            let next = numbers.len();
+            *numbers.entry(*root).or_insert(next)
+        })
+        .collect()
+}
+
+/// Escapes backslashes first, so the ones added for quotes and newlines stay single.
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl<'a, N, E, Ty: EdgeType> Export<'a, N, E, Ty> {
+    pub fn new(
+        graph: &'a Graph<N, E, Ty>,
+        node_label: impl Fn(NodeIndex, &N) -> String + 'a,
+    ) -> Self {
+        Export {
+            graph,
+            node_label: Box::new(node_label),
+            edge_label: None,
+            cluster: None,
+        }
+    }
+
+    pub fn edge_labels(self, label: impl Fn(&E) -> String + 'a) -> Self {
+        Export {
+            edge_label: Some(Box::new(label)),
+            ..self
+        }
+    }
+
+    /// Groups the nodes by the name `cluster` gives them, nodes without one stay ungrouped.
+    pub fn clusters(self, cluster: impl Fn(NodeIndex, &N) -> Option<String> + 'a) -> Self {
+        Export {
+            cluster: Some(Box::new(cluster)),
+            ..self
+        }
+    }
+
+    fn cluster_of(&self, node: NodeIndex) -> Option<String> {
+        self.cluster
+            .as_ref()
+            .and_then(|cluster| cluster(node, &self.graph[node]))
+    }
+
+    pub fn to_dot(&self) -> String {
+        let (kind, arrow) = if self.graph.is_directed() {
+            ("digraph", "->")
+        } else {
+            ("graph", "--")
+        };
+        let node_line = |node: NodeIndex| {
+            let label = (self.node_label)(node, &self.graph[node]);
+            format!("n{} [label=\"{}\"];\n", node.index(), escape_dot(&label))
+        };
+
+        let mut ret = format!("{} {{\n", kind);
+        // Clusters in the order their first node appears.
+        let mut clusters: Vec<(String, Vec<NodeIndex>)> = Vec::new();
+        let mut cluster_idx: HashMap<String, usize> = HashMap::new();
+        for node in self.graph.node_indices() {
+            match self.cluster_of(node) {
+                Some(name) => {
+                    let idx = *cluster_idx.entry(name.clone()).or_insert_with(|| {
+                        clusters.push((name, Vec::new()));
+                        clusters.len() - 1
+                    });
+                    clusters[idx].1.push(node);
+                }
+                None => ret += &format!("    {}", node_line(node)),
+            }
+        }
+        for (idx, (name, members)) in clusters.iter().enumerate() {
+            ret += &format!("    subgraph cluster_{} {{\n", idx);
+            ret += &format!("        label=\"{}\";\n", escape_dot(name));
+            for node in members {
+                ret += &format!("        {}", node_line(*node));
+            }
+            ret += "    }\n";
+        }
+        for edge in self.graph.edge_references() {
+            let attrs = match &self.edge_label {
+                Some(label) => format!(" [label=\"{}\"]", escape_dot(&label(edge.weight()))),
+                None => String::new(),
+            };
+            ret += &format!(
+                "    n{} {} n{}{};\n",
+                edge.source().index(),
+                arrow,
+                edge.target().index(),
+                attrs
+            );
+        }
+        ret += "}\n";
+        ret
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let mut ret = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        ret += "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n";
+        ret += "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n";
+        if self.cluster.is_some() {
+            ret +=
+                "  <key id=\"cluster\" for=\"node\" attr.name=\"cluster\" attr.type=\"string\"/>\n";
+        }
+        if self.edge_label.is_some() {
+            ret +=
+                "  <key id=\"edgelabel\" for=\"edge\" attr.name=\"label\" attr.type=\"string\"/>\n";
+        }
+        let direction = if self.graph.is_directed() {
+            "directed"
+        } else {
+            "undirected"
+        };
+        ret += &format!("  <graph id=\"G\" edgedefault=\"{}\">\n", direction);
+        for node in self.graph.node_indices() {
+            let label = (self.node_label)(node, &self.graph[node]);
+            ret += &format!(
+                "    <node id=\"n{}\"><data key=\"label\">{}</data>",
+                node.index(),
+                escape_xml(&label)
+            );
+            if let Some(cluster) = self.cluster_of(node) {
+                ret += &format!("<data key=\"cluster\">{}</data>", escape_xml(&cluster));
+            }
+            ret += "</node>\n";
+        }
+        for edge in self.graph.edge_references() {
+            ret += &format!(
+                "    <edge source=\"n{}\" target=\"n{}\">",
+                edge.source().index(),
+                edge.target().index()
+            );
+            if let Some(label) = &self.edge_label {
+                ret += &format!(
+                    "<data key=\"edgelabel\">{}</data>",
+                    escape_xml(&label(edge.weight()))
+                );
+            }
+            ret += "</edge>\n";
+        }
+        ret += "  </graph>\n</graphml>\n";
+        ret
+    }
+
+    /// Writes the graph to `path`, as DOT for `.dot` or `.gv` and as GraphML for `.graphml`.
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let contents = match path.extension().and_then(|e| e.to_str()) {
+            Some("dot" | "gv") => self.to_dot(),
+            Some("graphml") => self.to_graphml(),
+            _ => {
+                return Err(anyhow!(
+                    "{} is neither a .dot, .gv nor a .graphml file",
+                    path.display()
+                ))
+            }
+        };
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::graph::{from_grid, NodeMap};
+    use crate::table::{Dir4, Grid, TableIdx};
+
+    use lasso::Rodeo;
+    use ndarray::arr2;
+
+    #[fixture]
+    fn gates() -> (DiGraph<Spur, &'static str>, Rodeo) {
+        let mut rodeo = Rodeo::default();
+        let mut graph = DiGraph::new();
+        let [x, y, z] = ["x", "y", "z\"q"].map(|name| graph.add_node(rodeo.get_or_intern(name)));
+        graph.add_edge(x, z, "AND");
+        graph.add_edge(y, z, "AND");
+        (graph, rodeo)
+    }
+
+    #[rstest]
+    fn dot_with_names(gates: (DiGraph<Spur, &'static str>, Rodeo)) {
+        let (graph, rodeo) = gates;
+        let dot = Export::new(&graph, interned_labels(&rodeo))
+            .edge_labels(|op| op.to_string())
+            .to_dot();
+        assert_eq!(
+            dot,
+            "digraph {\n    n0 [label=\"x\"];\n    n1 [label=\"y\"];\n    n2 [label=\"z\\\"q\"];\n    \
+             n0 -> n2 [label=\"AND\"];\n    n1 -> n2 [label=\"AND\"];\n}\n"
+        );
+    }
+
+    #[test]
+    fn escape_dot_labels() {
+        assert_eq!(escape_dot("a\\\"b\nc"), "a\\\\\\\"b\\nc");
+    }
+
+    #[test]
+    fn dot_with_clusters() {
+        let grid: Grid<u8> = arr2(&[[1, 1, 2], [3, 3, 2]]).into();
+        let (graph, nodemap): (UnGraph<u8, ()>, NodeMap) =
+            from_grid(&grid, Dir4::ALL, |from, to| (from == to).then_some(()));
+        let parts = components(&graph);
+        assert_eq!(parts.iter().max(), Some(&2));
+        let dot = Export::new(&graph, position_labels(&nodemap))
+            .clusters(|node, plant| (*plant != 2).then(|| format!("part {}", parts[node.index()])))
+            .to_dot();
+        assert!(dot.starts_with("graph {\n    n2 [label=\"2,0\"];\n"));
+        assert!(dot.contains(
+            "    subgraph cluster_0 {\n        label=\"part 0\";\n        n0 [label=\"0,0\"];\n"
+        ));
+        assert!(dot.contains("n0 -- n1;"));
+        let first = nodemap.node(&TableIdx::new(0, 1)).unwrap();
+        assert!(dot.contains(&format!("n{} [label=\"0,1\"]", first.index())));
+        assert_eq!(dot.matches("subgraph").count(), 2);
+    }
+
+    #[rstest]
+    fn graphml(gates: (DiGraph<Spur, &'static str>, Rodeo)) {
+        let (graph, rodeo) = gates;
+        let xml = Export::new(&graph, interned_labels(&rodeo))
+            .edge_labels(|op| op.to_string())
+            .clusters(|node, _| Some((node.index() % 2).to_string()))
+            .to_graphml();
+        assert!(xml.contains("<graph id=\"G\" edgedefault=\"directed\">"));
+        assert!(xml.contains(
+            "<node id=\"n2\"><data key=\"label\">z&quot;q</data><data key=\"cluster\">0</data></node>"
+        ));
+        assert!(xml.contains(
+            "<edge source=\"n1\" target=\"n2\"><data key=\"edgelabel\">AND</data></edge>"
+        ));
+        assert!(xml.ends_with("</graphml>\n"));
+    }
+
+    #[rstest]
+    fn reject_unknown_format(gates: (DiGraph<Spur, &'static str>, Rodeo)) {
+        let (graph, rodeo) = gates;
+        let err = Export::new(&graph, interned_labels(&rodeo))
+            .save("gates.svg")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "gates.svg is neither a .dot, .gv nor a .graphml file"
+        );
+    }
+}
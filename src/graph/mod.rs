@@ -1,18 +1,172 @@
 use bimap::BiMap;
 
-use crate::table::TableIdx;
+use crate::table::{into_shape, Cells, Grid, TableDir, TableIdx};
 
-use petgraph::prelude::*;
+use ndarray::Array2;
+
+use petgraph::{prelude::*, EdgeType};
+
+pub mod dag;
+pub mod export;
+pub mod pathfinding;
 
 pub type NodeMap = BiMap<NodeIndex, TableIdx>;
 
-pub fn get_node_or_insert<Ty: petgraph::EdgeType>(pos: &TableIdx, graph: &mut Graph<(),(), Ty>, nodemap: &mut NodeMap) -> NodeIndex {
-    if let Some(n) = nodemap.get_by_right(pos) {
-        *n
+/// Lookup between graph nodes and the grid positions they stand for.
+pub trait PositionMap {
+    /// An empty map for positions inside a grid of the given size.
+    fn with_size(width: usize, height: usize) -> Self;
+
+    fn node(&self, pos: &TableIdx) -> Option<NodeIndex>;
+
+    fn position(&self, node: NodeIndex) -> Option<TableIdx>;
+
+    fn insert(&mut self, node: NodeIndex, pos: TableIdx);
+}
+
+impl PositionMap for NodeMap {
+    fn with_size(_width: usize, _height: usize) -> Self {
+        NodeMap::new()
+    }
+
+    fn node(&self, pos: &TableIdx) -> Option<NodeIndex> {
+        self.get_by_right(pos).copied()
+    }
+
+    fn position(&self, node: NodeIndex) -> Option<TableIdx> {
+        self.get_by_left(&node).copied()
+    }
+
+    fn insert(&mut self, node: NodeIndex, pos: TableIdx) {
+        BiMap::insert(self, node, pos);
+    }
+}
+
+/// A `PositionMap` backed by arrays, for graphs with about one node per grid cell.
+#[derive(Debug, Clone)]
+pub struct DenseNodeMap {
+    nodes: Array2<Option<NodeIndex>>,
+    positions: Vec<Option<TableIdx>>,
+}
+
+impl PositionMap for DenseNodeMap {
+    fn with_size(width: usize, height: usize) -> Self {
+        DenseNodeMap {
+            nodes: Array2::from_elem((height, width), None),
+            positions: Vec::with_capacity(width * height),
+        }
+    }
+
+    fn node(&self, pos: &TableIdx) -> Option<NodeIndex> {
+        *self.nodes.get(into_shape(*pos))?
+    }
+
+    fn position(&self, node: NodeIndex) -> Option<TableIdx> {
+        *self.positions.get(node.index())?
+    }
+
+    /// Panics if `pos` is outside of the grid the map was created for.
+    fn insert(&mut self, node: NodeIndex, pos: TableIdx) {
+        self.nodes[into_shape(pos)] = Some(node);
+        if self.positions.len() <= node.index() {
+            self.positions.resize(node.index() + 1, None);
+        }
+        self.positions[node.index()] = Some(pos);
+    }
+}
+
+/// The node standing for `pos`, added with the weight `weight` computes if there is none yet.
+pub fn get_node_or_insert<N, E, Ty: EdgeType, M: PositionMap>(
+    pos: &TableIdx,
+    graph: &mut Graph<N, E, Ty>,
+    nodemap: &mut M,
+    weight: impl FnOnce(&TableIdx) -> N,
+) -> NodeIndex {
+    if let Some(n) = nodemap.node(pos) {
+        n
     } else {
-        let n = graph.add_node(());
+        let n = graph.add_node(weight(pos));
         nodemap.insert(n, *pos);
         n
     }
 }
 
+/// Builds a graph with a node for every cell, weighted with a copy of the cell, and an edge
+/// from each cell to every neighbor in `neighborhood` for which `edge` returns a weight.
+/// Undirected graphs get a single edge per pair of cells.
+pub fn from_grid<T, E, Ty, M, S>(
+    grid: &Grid<T>,
+    neighborhood: S,
+    mut edge: impl FnMut(&T, &T) -> Option<E>,
+) -> (Graph<T, E, Ty>, M)
+where
+    T: Clone,
+    Ty: EdgeType,
+    M: PositionMap,
+    S: IntoIterator + Clone,
+    S::Item: Copy + Into<TableDir>,
+{
+    let mut graph = Graph::with_capacity(grid.width() * grid.height(), 0);
+    let mut nodemap = M::with_size(grid.width(), grid.height());
+    for (pos, cell) in grid.indexed_iter() {
+        let node = get_node_or_insert(&pos, &mut graph, &mut nodemap, |_| cell.clone());
+        for (neighborpos, neighbor) in grid.neighbors_with(pos, neighborhood.clone()) {
+            if let Some(weight) = edge(cell, neighbor) {
+                let neighbornode =
+                    get_node_or_insert(&neighborpos, &mut graph, &mut nodemap, |_| neighbor.clone());
+                graph.update_edge(node, neighbornode, weight);
+            }
+        }
+    }
+    (graph, nodemap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::Dir4;
+
+    use ndarray::arr2;
+
+    #[fixture]
+    fn grid() -> Grid<u8> {
+        arr2(&[[1, 2, 3], [1, 1, 4]]).into()
+    }
+
+    #[rstest]
+    fn directed_from_grid(grid: Grid<u8>) {
+        let (graph, nodemap): (DiGraph<u8, u8>, NodeMap) =
+            from_grid(&grid, Dir4::ALL, |from, to| (*to == from + 1).then_some(*to));
+        assert_eq!(graph.node_count(), 6);
+        assert_eq!(graph.edge_count(), 4);
+
+        let node = |x, y| nodemap.node(&TableIdx::new(x, y)).unwrap();
+        let edge = graph.find_edge(node(2, 0), node(2, 1)).unwrap();
+        assert_eq!(graph[edge], 4);
+        assert_eq!(graph[node(2, 0)], 3);
+        assert!(graph.find_edge(node(2, 1), node(2, 0)).is_none());
+    }
+
+    #[rstest]
+    fn undirected_from_grid(grid: Grid<u8>) {
+        let (graph, nodemap): (UnGraph<u8, ()>, DenseNodeMap) =
+            from_grid(&grid, Dir4::ALL, |from, to| (from == to).then_some(()));
+        assert_eq!(graph.edge_count(), 2);
+
+        let node = nodemap.node(&TableIdx::new(1, 1)).unwrap();
+        assert_eq!(nodemap.position(node), Some(TableIdx::new(1, 1)));
+        assert_eq!(nodemap.node(&TableIdx::new(3, 0)), None);
+    }
+
+    #[rstest]
+    fn weighted_nodes() {
+        let mut graph: UnGraph<usize, ()> = Graph::new_undirected();
+        let mut nodemap = DenseNodeMap::with_size(4, 4);
+        let a = get_node_or_insert(&TableIdx::new(3, 2), &mut graph, &mut nodemap, |pos| pos.x * pos.y);
+        let b = get_node_or_insert(&TableIdx::new(3, 2), &mut graph, &mut nodemap, |_| 0);
+        assert_eq!(a, b);
+        assert_eq!(graph[a], 6);
+    }
+}
@@ -0,0 +1,278 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Costs and shortest-path predecessors of every state a search reached.
+#[derive(Debug, Clone)]
+pub struct ShortestPaths<S, C> {
+    costs: HashMap<S, C>,
+    predecessors: HashMap<S, Vec<S>>,
+    goals: Vec<S>,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> ShortestPaths<S, C> {
+    fn new() -> Self {
+        Self {
+            costs: HashMap::new(),
+            predecessors: HashMap::new(),
+            goals: Vec::new(),
+        }
+    }
+
+    pub fn cost(&self, state: &S) -> Option<C> {
+        self.costs.get(state).copied()
+    }
+
+    /// The goal states reached at the lowest cost, in the order they were found.
+    pub fn goals(&self) -> &[S] {
+        &self.goals
+    }
+
+    /// The cost of the cheapest goal, if any was reached.
+    pub fn goal_cost(&self) -> Option<C> {
+        self.goals.first().and_then(|goal| self.cost(goal))
+    }
+
+    /// One shortest path from a start to `state`, start first. Follows the predecessor each
+    /// state was first reached from, which was settled earlier, so zero-cost cycles end.
+    pub fn path_to(&self, state: &S) -> Option<Vec<S>> {
+        self.costs.get(state)?;
+        let mut path = vec![state.clone()];
+        while let Some(prev) = self.predecessors.get(path.last().unwrap()).and_then(|p| p.first()) {
+            path.push(prev.clone());
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Every state that lies on some shortest path to one of `targets`, the targets included.
+    pub fn on_shortest_paths<'a>(&'a self, targets: impl IntoIterator<Item = &'a S>) -> HashSet<S> {
+        let mut seen = HashSet::new();
+        let mut todo: Vec<&S> = targets.into_iter().filter(|t| self.costs.contains_key(t)).collect();
+        while let Some(state) = todo.pop() {
+            if seen.insert(state.clone()) {
+                todo.extend(self.predecessors.get(state).into_iter().flatten());
+            }
+        }
+        seen
+    }
+}
+
+/// Breadth-first search where every step costs one. Stops once all states at the distance of
+/// the nearest goal have been looked at.
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> ShortestPaths<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut ret = ShortestPaths::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if ret.costs.insert(start.clone(), 0).is_none() {
+            queue.push_back((start, 0));
+        }
+    }
+
+    while let Some((state, dist)) = queue.pop_front() {
+        if ret.goal_cost().is_some_and(|best| dist > best) {
+            break;
+        }
+        if is_goal(&state) {
+            ret.goals.push(state);
+            continue;
+        }
+        for next in successors(&state) {
+            match ret.costs.get(&next) {
+                Some(&d) if d < dist + 1 => {}
+                Some(_) => ret.predecessors.entry(next).or_default().push(state.clone()),
+                None => {
+                    ret.costs.insert(next.clone(), dist + 1);
+                    ret.predecessors.insert(next.clone(), vec![state.clone()]);
+                    queue.push_back((next, dist + 1));
+                }
+            }
+        }
+    }
+    ret
+}
+
+/// Dijkstra's algorithm over non-negative step costs, see `astar`.
+pub fn dijkstra<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> ShortestPaths<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(starts, successors, |_| C::default(), is_goal)
+}
+
+/// A* search over non-negative step costs. `heuristic` must never overestimate the remaining
+/// cost to a goal and must not drop by more than the cost of a step, otherwise some shortest
+/// paths may be missed. Stops once nothing cheaper than the best goal is left. Start states
+/// never get predecessors, even when zero-cost steps lead back to them.
+pub fn astar<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+) -> ShortestPaths<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut ret = ShortestPaths::new();
+    // The heap holds indices into `queued` so states don't need to be `Ord`.
+    let mut queued: Vec<(S, C)> = Vec::new();
+    let mut heap = BinaryHeap::new();
+    let mut start_states = HashSet::new();
+    for start in starts {
+        if ret.costs.insert(start.clone(), C::default()).is_none() {
+            start_states.insert(start.clone());
+            heap.push(Reverse((heuristic(&start), queued.len())));
+            queued.push((start, C::default()));
+        }
+    }
+
+    while let Some(Reverse((estimate, id))) = heap.pop() {
+        let (state, cost) = queued[id].clone();
+        if ret.costs[&state] < cost {
+            continue;
+        }
+        if ret.goal_cost().is_some_and(|best| estimate > best) {
+            break;
+        }
+        if is_goal(&state) {
+            ret.goals.push(state);
+            continue;
+        }
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+            match ret.costs.get(&next) {
+                Some(&c) if c < next_cost || start_states.contains(&next) => {}
+                Some(&c) if c == next_cost => {
+                    ret.predecessors.entry(next).or_default().push(state.clone())
+                }
+                _ => {
+                    ret.costs.insert(next.clone(), next_cost);
+                    ret.predecessors.insert(next.clone(), vec![state.clone()]);
+                    heap.push(Reverse((next_cost + heuristic(&next), queued.len())));
+                    queued.push((next, next_cost));
+                }
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::{parse_char_table, Cells, Grid, TableIdx};
+
+    #[fixture]
+    fn maze() -> Grid<char> {
+        parse_char_table(
+            "\
+            S..#\n\
+            .#..\n\
+            ...E\n\
+            ",
+        )
+        .unwrap()
+        .into()
+    }
+
+    fn open_neighbors(maze: &Grid<char>, pos: TableIdx) -> Vec<TableIdx> {
+        maze.neighbors4(pos)
+            .filter(|(_, c)| **c != '#')
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    #[rstest]
+    fn bfs_all_paths(maze: Grid<char>) {
+        let start = TableIdx::new(0, 0);
+        let end = TableIdx::new(3, 2);
+        let paths = bfs([start], |pos| open_neighbors(&maze, *pos), |pos| *pos == end);
+
+        assert_eq!(paths.goal_cost(), Some(5));
+        assert_eq!(paths.goals(), [end]);
+        let path = paths.path_to(&end).unwrap();
+        assert_eq!(path.len(), 6);
+        assert_eq!((path[0], path[5]), (start, end));
+        // Every way around the wall in the middle is shortest.
+        assert_eq!(paths.on_shortest_paths([&end]).len(), 10);
+    }
+
+    #[rstest]
+    fn dijkstra_weighted(maze: Grid<char>) {
+        let end = TableIdx::new(3, 2);
+        // Lower rows are more expensive to enter, so the path along the top is preferred.
+        let successors = |pos: &TableIdx| {
+            open_neighbors(&maze, *pos)
+                .into_iter()
+                .map(|next| (next, 1 + next.y))
+                .collect::<Vec<_>>()
+        };
+        let paths = dijkstra([TableIdx::new(0, 0)], successors, |pos| *pos == end);
+
+        assert_eq!(paths.goal_cost(), Some(9));
+        assert_eq!(paths.on_shortest_paths([&end]).len(), 6);
+        assert_eq!(paths.cost(&TableIdx::new(2, 0)), Some(2));
+    }
+
+    #[rstest]
+    fn astar_matches_dijkstra(maze: Grid<char>) {
+        let end = TableIdx::new(3, 2);
+        let successors = |pos: &TableIdx| {
+            open_neighbors(&maze, *pos)
+                .into_iter()
+                .map(|next| (next, 1))
+                .collect::<Vec<_>>()
+        };
+        let manhattan = |pos: &TableIdx| end.x.abs_diff(pos.x) + end.y.abs_diff(pos.y);
+        let paths = astar([TableIdx::new(0, 0)], successors, manhattan, |pos| *pos == end);
+
+        assert_eq!(paths.goal_cost(), Some(5));
+        assert_eq!(paths.on_shortest_paths([&end]).len(), 10);
+    }
+
+    #[rstest]
+    fn zero_cost_cycles() {
+        // 0 and 1 as well as 2 and 3 are connected for free both ways.
+        let successors = |state: &usize| match state {
+            0 => vec![(1, 0)],
+            1 => vec![(0, 0), (2, 1)],
+            2 => vec![(1, 1), (3, 0)],
+            3 => vec![(2, 0)],
+            _ => unreachable!(),
+        };
+        let paths = dijkstra([0], successors, |state| *state == 3);
+
+        assert_eq!(paths.goal_cost(), Some(1));
+        assert_eq!(paths.path_to(&3), Some(vec![0, 1, 2, 3]));
+        assert_eq!(paths.path_to(&0), Some(vec![0]));
+        assert_eq!(paths.on_shortest_paths([&3]), HashSet::from([0, 1, 2, 3]));
+    }
+
+    #[rstest]
+    fn unreachable_goal(maze: Grid<char>) {
+        let paths = bfs([TableIdx::new(0, 0)], |pos| open_neighbors(&maze, *pos), |_| false);
+
+        assert_eq!(paths.goal_cost(), None);
+        assert_eq!(paths.cost(&TableIdx::new(3, 1)), Some(4));
+        assert_eq!(paths.path_to(&TableIdx::new(1, 1)), None);
+    }
+}
@@ -1,9 +1,13 @@
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use strum_macros::Display;
 
 use anyhow::Result;
 use std::fs::File;
-use std::path::PathBuf;
+#[cfg(feature = "serve")]
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "serve")]
+use std::time::Duration;
 
 mod prelude {
     use nom::{
@@ -19,10 +23,30 @@ mod prelude {
         pub part_b: Option<usize>,
     }
 
+    /// One `part a: ...` and `part b: ...` line for every part with an answer.
+    impl std::fmt::Display for AoCResult {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            if let Some(val) = self.part_a {
+                writeln!(f, "part a: {}", val)?;
+            }
+            if let Some(val) = self.part_b {
+                writeln!(f, "part b: {}", val)?;
+            }
+            Ok(())
+        }
+    }
+
     pub trait AoC {
         fn run(input: &str) -> anyhow::Result<AoCResult>;
     }
 
+    /// Writes a parsed model back into the puzzle input format it was parsed from.
+    ///
+    /// Parsing the returned string yields an equivalent model.
+    pub trait ToPuzzleString {
+        fn to_puzzle_string(&self) -> String;
+    }
+
     pub fn parse_usize(input: &str) -> IResult<&str, usize> {
         map_res(digit1, str::parse)(input)
     }
@@ -30,6 +54,22 @@ mod prelude {
     pub fn parse_isize(input: &str) -> IResult<&str, isize> {
         map_res(recognize(preceded(opt(tag("-")), digit1)), str::parse)(input)
     }
+
+    /// Quotes a string for JSON.
+    pub fn json_string(s: &str) -> String {
+        let mut ret = String::from('"');
+        for c in s.chars() {
+            match c {
+                '"' => ret.push_str("\\\""),
+                '\\' => ret.push_str("\\\\"),
+                '\n' => ret.push_str("\\n"),
+                c if c.is_control() => ret.push_str(&format!("\\u{:04x}", c as u32)),
+                c => ret.push(c),
+            }
+        }
+        ret.push('"');
+        ret
+    }
 }
 
 use crate::prelude::AoC;
@@ -51,7 +91,13 @@ mod day7;
 mod day8;
 mod day9;
 mod day24;
+#[cfg(test)]
+mod fuzz;
 mod graph;
+mod minimize;
+mod repl;
+#[cfg(feature = "serve")]
+mod serve;
 mod table;
 
 #[derive(ValueEnum, Clone, Debug, Display)]
@@ -75,41 +121,65 @@ enum Days {
     Day24,
 }
 
-fn run_day(day: Days, input: &str) -> Result<()> {
-    let result = match day {
-        Days::Day1 => crate::day1::Day::run(input),
-        Days::Day2 => crate::day2::Day::run(input),
-        Days::Day3 => crate::day3::Day::run(input),
-        Days::Day4 => crate::day4::Day::run(input),
-        Days::Day5 => crate::day5::Day::run(input),
-        Days::Day6 => crate::day6::Day::run(input),
-        Days::Day7 => crate::day7::Day::run(input),
-        Days::Day8 => crate::day8::Day::run(input),
-        Days::Day9 => crate::day9::Day::run(input),
-        Days::Day10 => crate::day10::Day::run(input),
-        Days::Day11 => crate::day11::Day::run(input),
-        Days::Day12 => crate::day12::Day::run(input),
-        Days::Day13 => crate::day13::Day::run(input),
-        Days::Day14 => crate::day14::Day::run(input),
-        Days::Day15 => crate::day15::Day::run(input),
-        Days::Day16 => crate::day16::Day::run(input),
-        Days::Day24 => crate::day24::Day::run(input),
-    }?;
-
-    if let Some(val) = result.part_a {
-        println!("part a: {}", val);
-    }
+/// Calls a function generic over a day's `Day` type with the type selected by `day`.
+macro_rules! with_day {
+    ($day:expr, $($func:ident)::+ $(, $arg:expr)*) => {
+        match $day {
+            Days::Day1 => $($func)::+::<crate::day1::Day>($($arg),*),
+            Days::Day2 => $($func)::+::<crate::day2::Day>($($arg),*),
+            Days::Day3 => $($func)::+::<crate::day3::Day>($($arg),*),
+            Days::Day4 => $($func)::+::<crate::day4::Day>($($arg),*),
+            Days::Day5 => $($func)::+::<crate::day5::Day>($($arg),*),
+            Days::Day6 => $($func)::+::<crate::day6::Day>($($arg),*),
+            Days::Day7 => $($func)::+::<crate::day7::Day>($($arg),*),
+            Days::Day8 => $($func)::+::<crate::day8::Day>($($arg),*),
+            Days::Day9 => $($func)::+::<crate::day9::Day>($($arg),*),
+            Days::Day10 => $($func)::+::<crate::day10::Day>($($arg),*),
+            Days::Day11 => $($func)::+::<crate::day11::Day>($($arg),*),
+            Days::Day12 => $($func)::+::<crate::day12::Day>($($arg),*),
+            Days::Day13 => $($func)::+::<crate::day13::Day>($($arg),*),
+            Days::Day14 => $($func)::+::<crate::day14::Day>($($arg),*),
+            Days::Day15 => $($func)::+::<crate::day15::Day>($($arg),*),
+            Days::Day16 => $($func)::+::<crate::day16::Day>($($arg),*),
+            Days::Day24 => $($func)::+::<crate::day24::Day>($($arg),*),
+        }
+    };
+}
 
-    if let Some(val) = result.part_b {
-        println!("part b: {}", val);
-    }
+fn run_day<T: AoC>(input: &str) -> Result<()> {
+    let result = T::run(input)?;
+
+    print!("{}", result);
 
     Ok(())
 }
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(value_enum, required = true)]
+    day: Option<Days>,
+
+    #[arg(short, long, default_value = "./input/")]
+    input: PathBuf,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Shrink an input as long as a predicate keeps holding for it
+    Minimize(MinimizeArgs),
+    /// Serve the solvers over HTTP, answering POST /days/<n> with the input as body
+    #[cfg(feature = "serve")]
+    Serve(ServeArgs),
+    /// Load a day's input and explore it with day-specific commands
+    Repl(InputArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct InputArgs {
     #[arg(value_enum)]
     day: Days,
 
@@ -117,22 +187,145 @@ struct Args {
     input: PathBuf,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+#[derive(clap::Args, Debug)]
+struct MinimizeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    /// Write the minimized input to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Keep inputs on which the solver panics
+    #[arg(
+        long,
+        required_unless_present_any = ["cmd", "differs_from"],
+        conflicts_with_all = ["cmd", "differs_from"]
+    )]
+    panics: bool,
+
+    /// Keep inputs for which this shell command exits successfully when fed the input on stdin
+    #[arg(long, conflicts_with = "differs_from")]
+    cmd: Option<String>,
+
+    /// Keep inputs for which the answers differ from what this shell command prints when fed
+    /// the input on stdin, e.g. a known good build run with `-i /dev/stdin <day>`
+    #[arg(long)]
+    differs_from: Option<String>,
+}
+
+#[cfg(feature = "serve")]
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
+    /// Address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8024")]
+    addr: String,
+
+    /// Largest accepted request body in bytes
+    #[arg(long, default_value_t = serve::Limits::default().max_body)]
+    max_body: usize,
 
+    /// Seconds a request may take before it is answered with a timeout
+    #[arg(long, default_value_t = serve::Limits::default().timeout.as_secs())]
+    timeout: u64,
+
+    /// Connections handled at once, more are turned away
+    #[arg(long, default_value_t = serve::Limits::default().max_connections)]
+    max_connections: usize,
+
+    /// Solvers running at once, including those still finishing after a timeout
+    #[arg(long, default_value_t = serve::Limits::default().max_solvers)]
+    max_solvers: usize,
+}
+
+fn read_input(day: &Days, input: &Path) -> Result<String> {
     let inputfilepath = {
-        if args.input.is_dir() {
-            args.input
-                .join(args.day.to_string().to_lowercase())
+        if input.is_dir() {
+            input
+                .join(day.to_string().to_lowercase())
                 .with_extension("txt")
         } else {
-            args.input
+            input.to_path_buf()
         }
     };
 
     let inputfile = File::open(&inputfilepath)?;
 
-    let inputstr = std::io::read_to_string(&inputfile)?;
+    Ok(std::io::read_to_string(&inputfile)?)
+}
+
+fn run_minimize(args: MinimizeArgs) -> Result<()> {
+    let inputstr = read_input(&args.input.day, &args.input.input)?;
+
+    let predicate = match (args.cmd, args.differs_from) {
+        (Some(cmd), _) => minimize::Predicate::Command(cmd),
+        (_, Some(reference)) => minimize::Predicate::DiffersFrom(reference),
+        (None, None) => minimize::Predicate::Panics,
+    };
+
+    let minimized = with_day!(args.input.day, minimize::minimize_day, &inputstr, &predicate)?;
+
+    match args.output {
+        Some(path) => std::fs::write(path, minimized)?,
+        None => print!("{}", minimized),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "serve")]
+fn solver<T: AoC>() -> serve::Solver {
+    T::run
+}
+
+#[cfg(feature = "serve")]
+fn lookup_solver(day: usize) -> Option<serve::Solver> {
+    let day = Days::from_str(&format!("day{}", day), true).ok()?;
+    Some(with_day!(day, solver))
+}
+
+#[cfg(feature = "serve")]
+fn run_serve(args: ServeArgs) -> Result<()> {
+    let limits = serve::Limits {
+        max_body: args.max_body,
+        timeout: Duration::from_secs(args.timeout),
+        max_connections: args.max_connections,
+        max_solvers: args.max_solvers,
+    };
+    let listener = TcpListener::bind(&args.addr)?;
+    eprintln!("listening on {}", listener.local_addr()?);
+    Ok(serve::serve(listener, limits, lookup_solver)?)
+}
 
-    run_day(args.day, &inputstr)
+fn run_repl(args: InputArgs) -> Result<()> {
+    let inputstr = read_input(&args.day, &args.input)?;
+    let stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout();
+
+    match args.day {
+        Days::Day6 => repl::repl::<crate::day6::Inspector>(&inputstr, stdin, &mut stdout),
+        Days::Day10 => repl::repl::<crate::day10::Inspector>(&inputstr, stdin, &mut stdout),
+        Days::Day12 => repl::repl::<crate::day12::Inspector>(&inputstr, stdin, &mut stdout),
+        Days::Day14 => repl::repl::<crate::day14::Inspector>(&inputstr, stdin, &mut stdout),
+        Days::Day15 => repl::repl::<crate::day15::Inspector>(&inputstr, stdin, &mut stdout),
+        Days::Day16 => repl::repl::<crate::day16::Inspector>(&inputstr, stdin, &mut stdout),
+        Days::Day24 => repl::repl::<crate::day24::Inspector>(&inputstr, stdin, &mut stdout),
+        day => Err(anyhow::anyhow!("{} has no inspection commands", day)),
+    }
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+
+    match args.command {
+        Some(Command::Minimize(minimize_args)) => run_minimize(minimize_args),
+        #[cfg(feature = "serve")]
+        Some(Command::Serve(serve_args)) => run_serve(serve_args),
+        Some(Command::Repl(repl_args)) => run_repl(repl_args),
+        None => {
+            let day = args.day.expect("day is a required argument");
+            let inputstr = read_input(&day, &args.input)?;
+            with_day!(day, run_day, &inputstr)
+        }
+    }
 }
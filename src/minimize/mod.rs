@@ -0,0 +1,252 @@
+use crate::prelude::*;
+
+use std::io::{ErrorKind, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::process::{Command, Output, Stdio};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+/// What makes an input worth keeping while it is being shrunk.
+#[derive(Debug, Clone)]
+pub enum Predicate {
+    /// Solving the input panics.
+    Panics,
+    /// The shell command exits successfully when fed the input on stdin.
+    Command(String),
+    /// The answers of the solver, printed as `part a: ...` and `part b: ...` lines, differ from
+    /// what the reference shell command prints when fed the input on stdin. A failing solver
+    /// differs too, a failing reference doesn't.
+    DiffersFrom(String),
+}
+
+impl Predicate {
+    fn holds<T: AoC>(&self, input: &str) -> bool {
+        match self {
+            Predicate::Panics => catch_unwind(AssertUnwindSafe(|| T::run(input))).is_err(),
+            Predicate::Command(cmd) => {
+                run_command(cmd, input, Stdio::null()).is_ok_and(|output| output.status.success())
+            }
+            Predicate::DiffersFrom(cmd) => {
+                let answers = catch_unwind(AssertUnwindSafe(|| T::run(input)))
+                    .ok()
+                    .and_then(Result::ok)
+                    .map(|result| result.to_string());
+                run_command(cmd, input, Stdio::piped()).is_ok_and(|reference| {
+                    reference.status.success()
+                        && answers.as_deref() != Some(&*String::from_utf8_lossy(&reference.stdout))
+                })
+            }
+        }
+    }
+}
+
+/// Runs the shell command with the input on stdin. Commands may exit without reading all of
+/// it, only their exit status and output count then.
+fn run_command(cmd: &str, input: &str, stdout: Stdio) -> anyhow::Result<Output> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::piped())
+        .stdout(stdout)
+        .spawn()?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or(anyhow!("couldn't open stdin of {}", cmd))?;
+    // Writing from another thread keeps a command that prints before it has read everything
+    // from blocking on a full stdout pipe.
+    let (written, output) = std::thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output();
+        (writer.join().expect("writing to a pipe doesn't panic"), output)
+    });
+    match written {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(output?),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Granularity {
+    Paragraphs,
+    Lines,
+}
+
+impl Granularity {
+    fn split(self, text: &str) -> Vec<&str> {
+        match self {
+            Granularity::Paragraphs => text.trim_end_matches('\n').split("\n\n").collect(),
+            Granularity::Lines => text.lines().collect(),
+        }
+    }
+
+    fn join(self, units: &[&str]) -> String {
+        let sep = match self {
+            Granularity::Paragraphs => "\n\n",
+            Granularity::Lines => "\n",
+        };
+        let mut ret = units.join(sep);
+        ret.push('\n');
+        ret
+    }
+}
+
+/// Parses and serializes the candidate, so that only valid inputs are ever handed to the
+/// predicate.
+fn normalize<T>(input: &str) -> Option<String>
+where
+    T: FromStr + ToPuzzleString,
+{
+    Some(input.parse::<T>().ok()?.to_puzzle_string())
+}
+
+/// Removes ever smaller chunks of units from the text as long as the result stays valid and
+/// interesting.
+fn shrink<T, P>(mut text: String, granularity: Granularity, interesting: &mut P) -> String
+where
+    T: FromStr + ToPuzzleString,
+    P: FnMut(&str) -> bool,
+{
+    let mut chunks = 2;
+    loop {
+        let units = granularity.split(&text);
+        if units.len() < 2 {
+            return text;
+        }
+        chunks = chunks.min(units.len());
+        let chunk_size = units.len().div_ceil(chunks);
+
+        let reduced = (0..units.len()).step_by(chunk_size).find_map(|start| {
+            let candidate: Vec<&str> = units[..start]
+                .iter()
+                .chain(units[(start + chunk_size).min(units.len())..].iter())
+                .copied()
+                .collect();
+            let candidate = normalize::<T>(&granularity.join(&candidate))?;
+            interesting(&candidate).then_some(candidate)
+        });
+
+        match reduced {
+            Some(candidate) => {
+                text = candidate;
+                chunks = (chunks - 1).max(2);
+            }
+            None if chunks >= units.len() => return text,
+            None => chunks *= 2,
+        }
+    }
+}
+
+/// Shrinks the input by dropping whole blocks (e.g. machines) and then single lines (e.g.
+/// robots, gates or grid rows), keeping only candidates that parse and are interesting.
+pub fn minimize<T, P>(input: &str, mut interesting: P) -> anyhow::Result<String>
+where
+    T: FromStr + ToPuzzleString,
+    P: FnMut(&str) -> bool,
+{
+    let text = normalize::<T>(input).ok_or(anyhow!("input does not parse"))?;
+    if !interesting(&text) {
+        return Err(anyhow!("input is not interesting to begin with"));
+    }
+
+    let text = shrink::<T, P>(text, Granularity::Paragraphs, &mut interesting);
+    Ok(shrink::<T, P>(text, Granularity::Lines, &mut interesting))
+}
+
+/// Minimizes the input of a day with respect to the predicate, silencing panics of the
+/// solver in the meantime.
+pub fn minimize_day<T>(input: &str, predicate: &Predicate) -> anyhow::Result<String>
+where
+    T: AoC + FromStr + ToPuzzleString,
+{
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let ret = minimize::<T, _>(input, |candidate| predicate.holds::<T>(candidate));
+    std::panic::set_hook(hook);
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    fn minimize_robots() {
+        let input = "\
+            p=0,4 v=3,-3\n\
+            p=6,3 v=-1,-3\n\
+            p=10,3 v=-1,2\n\
+            p=2,4 v=2,-3\n\
+            p=9,5 v=-3,-3\n\
+        ";
+        let result = minimize::<crate::day14::Day, _>(input, |s| s.contains("p=2,4")).unwrap();
+        assert_eq!(result, "p=2,4 v=2,-3\n")
+    }
+
+    #[rstest]
+    fn minimize_machines() {
+        let input = "\
+            Button A: X+94, Y+34\n\
+            Button B: X+22, Y+67\n\
+            Prize: X=8400, Y=5400\n\
+            \n\
+            Button A: X+26, Y+66\n\
+            Button B: X+67, Y+21\n\
+            Prize: X=12748, Y=12176\n\
+            \n\
+            Button A: X+17, Y+86\n\
+            Button B: X+84, Y+37\n\
+            Prize: X=7870, Y=6450\n\
+        ";
+        let result = minimize::<crate::day13::Day, _>(input, |s| s.contains("X+67")).unwrap();
+        assert_eq!(
+            result,
+            "\
+            Button A: X+26, Y+66\n\
+            Button B: X+67, Y+21\n\
+            Prize: X=12748, Y=12176\n\
+            "
+        )
+    }
+
+    #[rstest]
+    fn minimize_grid_rows() {
+        let input = "\
+            AAAA\n\
+            BBCD\n\
+            BBCC\n\
+            EEEC\n\
+        ";
+        let result = minimize::<crate::day12::Day, _>(input, |s| s.contains('D')).unwrap();
+        assert_eq!(result, "BBCD\n")
+    }
+
+    #[rstest]
+    #[case("true", true)]
+    #[case("false", false)]
+    #[case("head -c 1 >/dev/null", true)]
+    fn command_ignores_unread_input(#[case] cmd: &str, #[case] holds: bool) {
+        // Much more than fits into a pipe, so writing fails once the command is gone.
+        let input = "p=0,4 v=3,-3\n".repeat(100_000);
+        let predicate = Predicate::Command(cmd.to_string());
+        assert_eq!(predicate.holds::<crate::day14::Day>(&input), holds)
+    }
+
+    #[rstest]
+    #[case("printf 'part a: 11\\npart b: 31\\n'", false)]
+    #[case("printf 'part a: 11\\npart b: 30\\n'", true)]
+    #[case("cat >/dev/null; exit 1", false)]
+    fn differs_from_reference(#[case] cmd: &str, #[case] holds: bool) {
+        let input = "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n";
+        let predicate = Predicate::DiffersFrom(cmd.to_string());
+        assert_eq!(predicate.holds::<crate::day1::Day>(input), holds)
+    }
+
+    #[rstest]
+    fn uninteresting_input() {
+        let input = "p=0,4 v=3,-3\n";
+        assert!(minimize::<crate::day14::Day, _>(input, |_| false).is_err())
+    }
+}
@@ -0,0 +1,112 @@
+use std::io::{BufRead, Write};
+
+/// Day-specific state that can be poked at interactively.
+pub trait Inspect: Sized {
+    fn load(input: &str) -> anyhow::Result<Self>;
+
+    /// Usage and description of every command understood by `exec`.
+    fn commands(&self) -> &'static [(&'static str, &'static str)];
+
+    /// Runs a single command and returns what should be shown to the user.
+    fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String>;
+}
+
+fn help<T: Inspect>(inspector: &T) -> String {
+    let mut ret = String::new();
+    for (usage, description) in inspector
+        .commands()
+        .iter()
+        .chain(&[("help", "show this help"), ("quit", "leave the repl")])
+    {
+        ret += &format!("{:<20} {}\n", usage, description);
+    }
+    ret
+}
+
+/// Loads the input and executes commands read line by line until `quit` or end of input.
+pub fn repl<T: Inspect>(input: &str, commands: impl BufRead, out: &mut impl Write) -> anyhow::Result<()> {
+    let mut inspector = T::load(input)?;
+
+    write!(out, "> ")?;
+    out.flush()?;
+    for line in commands.lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => {}
+            ["quit" | "exit"] => break,
+            ["help"] => write!(out, "{}", help(&inspector))?,
+            [command, args @ ..] => match inspector.exec(command, args) {
+                Ok(output) => writeln!(out, "{}", output.trim_end())?,
+                Err(e) => writeln!(out, "error: {}", e)?,
+            },
+        }
+        write!(out, "> ")?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// Parses the single numeric argument of a command, falling back to `default` if it is
+/// omitted.
+pub fn parse_arg<T: std::str::FromStr>(args: &[&str], default: T) -> anyhow::Result<T> {
+    match args {
+        [] => Ok(default),
+        [arg] => arg
+            .parse()
+            .map_err(|_| anyhow::anyhow!("{} is not a valid argument", arg)),
+        _ => Err(anyhow::anyhow!("expected at most one argument")),
+    }
+}
+
+/// The error for a command line no inspector understands.
+pub fn unknown(command: &str, args: &[&str]) -> anyhow::Error {
+    anyhow::anyhow!("unknown command {}, try help", [&[command], args].concat().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    struct Counter(usize);
+
+    impl Inspect for Counter {
+        fn load(input: &str) -> anyhow::Result<Self> {
+            Ok(Counter(input.trim().parse()?))
+        }
+
+        fn commands(&self) -> &'static [(&'static str, &'static str)] {
+            &[("add [N]", "add N to the counter")]
+        }
+
+        fn exec(&mut self, command: &str, args: &[&str]) -> anyhow::Result<String> {
+            match command {
+                "add" => {
+                    self.0 += parse_arg(args, 1)?;
+                    Ok(self.0.to_string())
+                }
+                _ => Err(unknown(command, args)),
+            }
+        }
+    }
+
+    #[rstest]
+    fn run_commands() {
+        let mut out = Vec::new();
+        repl::<Counter>("5\n", "add\nadd 3\nadd x\nfoo\nquit\nadd\n".as_bytes(), &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "> 6\n> 9\n> error: x is not a valid argument\n> error: unknown command foo, try help\n> "
+        )
+    }
+
+    #[rstest]
+    fn show_help() {
+        let mut out = Vec::new();
+        repl::<Counter>("0\n", "help\n".as_bytes(), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("add [N]"));
+        assert!(out.contains("quit"));
+    }
+}
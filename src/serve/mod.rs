@@ -0,0 +1,395 @@
+use crate::prelude::*;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+pub type Solver = fn(&str) -> anyhow::Result<AoCResult>;
+
+pub type SolverLookup = fn(usize) -> Option<Solver>;
+
+/// Largest accepted request line and headers together, in bytes.
+const MAX_HEAD: u64 = 8 << 10;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_body: usize,
+    pub timeout: Duration,
+    /// Connections handled at once, more are answered with 503.
+    pub max_connections: usize,
+    /// Solvers running at once, including those still finishing after a timeout.
+    pub max_solvers: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_body: 1 << 20,
+            timeout: Duration::from_secs(30),
+            max_connections: 64,
+            max_solvers: 4,
+        }
+    }
+}
+
+/// Counts the threads of one kind and refuses to hand out more than `max` slots at a time.
+#[derive(Debug, Clone)]
+struct Slots {
+    used: Arc<AtomicUsize>,
+    max: usize,
+}
+
+/// Gives its slot back when dropped.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slots {
+    fn new(max: usize) -> Self {
+        Self {
+            used: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    fn acquire(&self) -> Option<Slot> {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| (used < self.max).then_some(used + 1))
+            .ok()?;
+        Some(Slot(self.used.clone()))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Response {
+    status: u16,
+    reason: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn error(status: u16, reason: &'static str, msg: &str) -> Self {
+        Self {
+            status,
+            reason,
+            body: format!("{{\"error\":{}}}", json_string(msg)),
+        }
+    }
+
+    fn write_to(&self, stream: &mut TcpStream) -> std::io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason,
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+fn json_answer(answer: Option<usize>) -> String {
+    answer.map_or("null".to_string(), |val| val.to_string())
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: String,
+}
+
+fn read_request(stream: &TcpStream, limits: &Limits) -> Result<Request, Response> {
+    let bad_request = |msg: &str| Response::error(400, "Bad Request", msg);
+    let mut reader = BufReader::new(stream).take(MAX_HEAD);
+    let mut read_line = |line: &mut String| -> Result<(), Response> {
+        reader.read_line(line).map_err(|e| bad_request(&e.to_string()))?;
+        if !line.ends_with('\n') {
+            return Err(Response::error(
+                431,
+                "Request Header Fields Too Large",
+                &format!("request head exceeds {} bytes", MAX_HEAD),
+            ));
+        }
+        Ok(())
+    };
+
+    let mut request_line = String::new();
+    read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("malformed request line"));
+    };
+
+    let mut content_length = None;
+    loop {
+        let mut header = String::new();
+        read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| bad_request("invalid Content-Length"))?,
+                );
+            }
+        }
+    }
+
+    let body = match content_length {
+        Some(len) if len > limits.max_body => {
+            return Err(Response::error(
+                413,
+                "Payload Too Large",
+                &format!("request body exceeds {} bytes", limits.max_body),
+            ));
+        }
+        Some(len) => {
+            let mut body = vec![0; len];
+            reader.set_limit(len as u64);
+            reader
+                .read_exact(&mut body)
+                .map_err(|e| bad_request(&e.to_string()))?;
+            String::from_utf8(body).map_err(|_| bad_request("request body is not utf-8"))?
+        }
+        None if method == "POST" => {
+            return Err(Response::error(411, "Length Required", "Content-Length is required"));
+        }
+        None => String::new(),
+    };
+
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        body,
+    })
+}
+
+/// Runs the solver on its own thread, holding one of `slots` until it finishes. A solver
+/// exceeding the timeout is left to finish in the background, but its answer is discarded.
+fn solve(
+    solver: Solver,
+    input: String,
+    timeout: Duration,
+    slots: &Slots,
+) -> Result<(AoCResult, Duration), Response> {
+    let slot = slots
+        .acquire()
+        .ok_or(Response::error(503, "Service Unavailable", "too many solvers are running"))?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _slot = slot;
+        let start = Instant::now();
+        let result = solver(&input);
+        let _ = tx.send(result.map(|r| (r, start.elapsed())));
+    });
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(result)) => Ok(result),
+        Ok(Err(e)) => Err(Response::error(422, "Unprocessable Entity", &e.to_string())),
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(Response::error(
+            504,
+            "Gateway Timeout",
+            &format!("solver took longer than {:?}", timeout),
+        )),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err(Response::error(500, "Internal Server Error", "solver panicked"))
+        }
+    }
+}
+
+fn handle(request: Request, limits: &Limits, solvers: SolverLookup, solver_slots: &Slots) -> Response {
+    let Some(day) = request.path.strip_prefix("/days/") else {
+        return Response::error(404, "Not Found", &format!("no route for {}", request.path));
+    };
+    let Some((day, solver)) = day.parse().ok().and_then(|n| solvers(n).map(|solver| (n, solver))) else {
+        return Response::error(404, "Not Found", &format!("no solver for day {}", day));
+    };
+    if request.method != "POST" {
+        return Response::error(405, "Method Not Allowed", "only POST is supported");
+    }
+
+    match solve(solver, request.body, limits.timeout, solver_slots) {
+        Ok((result, elapsed)) => Response {
+            status: 200,
+            reason: "OK",
+            body: format!(
+                "{{\"day\":{},\"part_a\":{},\"part_b\":{},\"elapsed_us\":{}}}",
+                day,
+                json_answer(result.part_a),
+                json_answer(result.part_b),
+                elapsed.as_micros()
+            ),
+        },
+        Err(response) => response,
+    }
+}
+
+fn handle_connection(
+    mut stream: TcpStream,
+    limits: Limits,
+    solvers: SolverLookup,
+    solver_slots: &Slots,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(limits.timeout))?;
+    let response = match read_request(&stream, &limits) {
+        Ok(request) => handle(request, &limits, solvers, solver_slots),
+        Err(response) => response,
+    };
+    response.write_to(&mut stream)?;
+    // Take in whatever the client still sends, such as a rejected body, so that closing the
+    // connection doesn't reset it before the response arrived.
+    stream.shutdown(Shutdown::Write)?;
+    let _ = std::io::copy(&mut (&stream).take(MAX_HEAD + limits.max_body as u64), &mut std::io::sink());
+    Ok(())
+}
+
+/// Answers `POST /days/{n}` requests carrying the puzzle input as body with the solver's
+/// answers and timing as JSON, one thread per connection up to `limits.max_connections`.
+pub fn serve(listener: TcpListener, limits: Limits, solvers: SolverLookup) -> std::io::Result<()> {
+    let connection_slots = Slots::new(limits.max_connections);
+    let solver_slots = Slots::new(limits.max_solvers);
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let Some(slot) = connection_slots.acquire() else {
+            let busy = Response::error(503, "Service Unavailable", "too many open connections");
+            let ready = stream.set_write_timeout(Some(limits.timeout));
+            if let Err(e) = ready.and_then(|_| busy.write_to(&mut stream)) {
+                eprintln!("connection failed: {}", e);
+            }
+            continue;
+        };
+        let solver_slots = solver_slots.clone();
+        std::thread::spawn(move || {
+            let _slot = slot;
+            if let Err(e) = handle_connection(stream, limits, solvers, &solver_slots) {
+                eprintln!("connection failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use std::net::SocketAddr;
+
+    fn sleepy(_input: &str) -> anyhow::Result<AoCResult> {
+        std::thread::sleep(Duration::from_secs(5));
+        Ok(AoCResult {
+            part_a: None,
+            part_b: None,
+        })
+    }
+
+    fn test_solvers(day: usize) -> Option<Solver> {
+        match day {
+            1 => Some(crate::day1::Day::run),
+            99 => Some(sleepy),
+            _ => None,
+        }
+    }
+
+    #[fixture]
+    fn server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let limits = Limits {
+            max_body: 64,
+            timeout: Duration::from_millis(200),
+            max_connections: 2,
+            max_solvers: 1,
+        };
+        std::thread::spawn(move || serve(listener, limits, test_solvers));
+        addr
+    }
+
+    fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[rstest]
+    fn solve_day(server: SocketAddr) {
+        let response = request(server, "POST", "/days/1", "3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n");
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("\"part_a\":11,\"part_b\":31,\"elapsed_us\":"), "{}", response);
+    }
+
+    #[rstest]
+    #[case("/days/01")]
+    #[case("/days/+1")]
+    fn canonical_day(server: SocketAddr, #[case] path: &str) {
+        let response = request(server, "POST", path, "1   2\n");
+        assert!(response.contains("{\"day\":1,\"part_a\":"), "{}", response);
+    }
+
+    #[rstest]
+    #[case("POST", "/days/2", "", "404")]
+    #[case("POST", "/nope", "", "404")]
+    #[case("GET", "/days/1", "", "405")]
+    #[case("POST", "/days/1", "garbage", "422")]
+    #[case("POST", "/days/1", &"1   2\n".repeat(20), "413")]
+    #[case("POST", "/days/99", "", "504")]
+    fn rejected(server: SocketAddr, #[case] method: &str, #[case] path: &str, #[case] body: &str, #[case] status: &str) {
+        let response = request(server, method, path, body);
+        assert!(response.starts_with(&format!("HTTP/1.1 {}", status)), "{}", response);
+        assert!(response.contains("{\"error\":"), "{}", response);
+    }
+
+    #[rstest]
+    fn reject_long_head(server: SocketAddr) {
+        let path = format!("/days/{}", "1".repeat(MAX_HEAD as usize));
+        let response = request(server, "POST", &path, "");
+        assert!(response.starts_with("HTTP/1.1 431"), "{}", response);
+    }
+
+    #[rstest]
+    fn reject_busy_solvers(server: SocketAddr) {
+        // The first solver keeps its slot after timing out, until it is done sleeping.
+        assert!(request(server, "POST", "/days/99", "").starts_with("HTTP/1.1 504"));
+        let response = request(server, "POST", "/days/1", "1   2\n");
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+    }
+
+    #[rstest]
+    fn reject_busy_connections(server: SocketAddr) {
+        // Connections that send nothing hold their slots until the read times out, and the
+        // server accepts connections in order, so the third one finds no slot left.
+        let _idle = [TcpStream::connect(server).unwrap(), TcpStream::connect(server).unwrap()];
+        let mut stream = TcpStream::connect(server).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+        assert!(response.contains("too many open connections"), "{}", response);
+    }
+
+    #[rstest]
+    #[case("a\"b\\c\n", "\"a\\\"b\\\\c\\n\"")]
+    fn escape_json(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(json_string(input), expected)
+    }
+}
@@ -0,0 +1,357 @@
+use ndarray::Array2;
+
+use super::{into_shape, shift_within, Dir4, Dir8, PlaneIdx, TableDir, TableIdx};
+
+/// Cells at positions that can be stepped between, shared by `Grid`, `Torus` and `SparseGrid`.
+pub trait Cells {
+    type Pos: Copy;
+    type Cell;
+
+    /// Moves `pos` by `step`, or `None` if there is no such position.
+    fn shift(&self, pos: Self::Pos, step: TableDir) -> Option<Self::Pos>;
+
+    fn get(&self, pos: Self::Pos) -> Option<&Self::Cell>;
+
+    /// The position a point of the plane stands for, or `None` if it has no place here.
+    fn locate(&self, point: PlaneIdx) -> Option<Self::Pos>;
+
+    /// Smallest and largest corner of the area cells can be found in, both inclusive, or
+    /// `None` if they may be anywhere.
+    fn extent(&self) -> Option<(PlaneIdx, PlaneIdx)>;
+
+    /// The cells one step of the stencil away from `pos`. Positions without a cell are skipped.
+    fn neighbors_with<S>(&self, pos: Self::Pos, stencil: S) -> Neighbors<'_, Self, S::IntoIter>
+    where
+        Self: Sized,
+        S: IntoIterator,
+        S::Item: Copy + Into<TableDir>,
+    {
+        Neighbors {
+            cells: self,
+            pos,
+            stencil: stencil.into_iter(),
+        }
+    }
+
+    /// The orthogonally adjacent cells of `pos`.
+    fn neighbors4(&self, pos: Self::Pos) -> Neighbors<'_, Self, std::array::IntoIter<Dir4, 4>>
+    where
+        Self: Sized,
+    {
+        self.neighbors_with(pos, Dir4::ALL)
+    }
+
+    /// The orthogonally and diagonally adjacent cells of `pos`.
+    fn neighbors8(&self, pos: Self::Pos) -> Neighbors<'_, Self, std::array::IntoIter<Dir8, 8>>
+    where
+        Self: Sized,
+    {
+        self.neighbors_with(pos, Dir8::ALL)
+    }
+}
+
+impl<A> Cells for Array2<A> {
+    type Pos = TableIdx;
+    type Cell = A;
+
+    fn shift(&self, pos: TableIdx, step: TableDir) -> Option<TableIdx> {
+        shift_within(pos, step, self.dim())
+    }
+
+    fn get(&self, pos: TableIdx) -> Option<&A> {
+        Array2::get(self, into_shape(pos))
+    }
+
+    fn locate(&self, point: PlaneIdx) -> Option<TableIdx> {
+        shift_within(TableIdx::zero(), point, self.dim())
+    }
+
+    fn extent(&self) -> Option<(PlaneIdx, PlaneIdx)> {
+        let (rows, columns) = self.dim();
+        Some((PlaneIdx::zero(), PlaneIdx::new(columns as isize - 1, rows as isize - 1)))
+    }
+}
+
+/// Iterator over the neighbors of a cell, see `Cells::neighbors_with`.
+pub struct Neighbors<'a, C: Cells, S> {
+    cells: &'a C,
+    pos: C::Pos,
+    stencil: S,
+}
+
+impl<'a, C, S> Neighbors<'a, C, S>
+where
+    C: Cells,
+    S: Iterator,
+    S::Item: Copy + Into<TableDir>,
+{
+    /// Also yields the stencil entry that led to every neighbor.
+    pub fn with_dirs(self) -> impl Iterator<Item = (S::Item, C::Pos, &'a C::Cell)> {
+        let Neighbors { cells, pos, stencil } = self;
+        stencil.filter_map(move |dir| {
+            let next = cells.shift(pos, dir.into())?;
+            Some((dir, next, cells.get(next)?))
+        })
+    }
+}
+
+impl<'a, C, S> Iterator for Neighbors<'a, C, S>
+where
+    C: Cells,
+    S: Iterator,
+    S::Item: Copy + Into<TableDir>,
+{
+    type Item = (C::Pos, &'a C::Cell);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for dir in self.stencil.by_ref() {
+            if let Some(next) = self.cells.shift(self.pos, dir.into()) {
+                if let Some(cell) = self.cells.get(next) {
+                    return Some((next, cell));
+                }
+            }
+        }
+        None
+    }
+}
+
+type CellPredicate<'a, T> = Box<dyn FnMut(&T) -> bool + 'a>;
+
+/// Iterator over the cells in a straight line, see `cast_ray`.
+pub struct Ray<'a, C: Cells> {
+    cells: &'a C,
+    origin: PlaneIdx,
+    point: PlaneIdx,
+    direction: TableDir,
+    max_len: Option<usize>,
+    /// Points left to walk in the current direction, counting those without a cell.
+    steps_left: Option<usize>,
+    stop_before: Option<CellPredicate<'a, C::Cell>>,
+    stop_at: Option<CellPredicate<'a, C::Cell>>,
+    both_ways: bool,
+    done: bool,
+}
+
+impl<'a, C: Cells> Ray<'a, C> {
+    /// Walks at most `len` points from the origin on, including those without a cell.
+    pub fn max_len(mut self, len: usize) -> Self {
+        self.max_len = Some(len);
+        self.steps_left = Some(len);
+        self
+    }
+
+    /// Ends right before the first cell that satisfies the predicate, like a wall.
+    pub fn stop_before(mut self, predicate: impl FnMut(&C::Cell) -> bool + 'a) -> Self {
+        self.stop_before = Some(Box::new(predicate));
+        self
+    }
+
+    /// Ends with the first cell that satisfies the predicate.
+    pub fn stop_at(mut self, predicate: impl FnMut(&C::Cell) -> bool + 'a) -> Self {
+        self.stop_at = Some(Box::new(predicate));
+        self
+    }
+
+    /// Once done, walks from the origin in the opposite direction as well, with the same
+    /// limits. The origin is only yielded once.
+    pub fn both_ways(mut self) -> Self {
+        self.both_ways = true;
+        self
+    }
+
+    /// Whether `point` is outside of the extent and moving away from it, or standing still.
+    fn has_left(&self) -> bool {
+        let Some((min, max)) = self.cells.extent() else {
+            return false;
+        };
+        let (point, dir) = (self.point, self.direction);
+        (point.x < min.x && dir.x <= 0)
+            || (point.x > max.x && dir.x >= 0)
+            || (point.y < min.y && dir.y <= 0)
+            || (point.y > max.y && dir.y >= 0)
+    }
+
+    fn finish_direction(&mut self) {
+        if self.both_ways {
+            self.both_ways = false;
+            self.direction = -self.direction;
+            self.point = self.origin + self.direction;
+            self.steps_left = self.max_len.map(|len| len.saturating_sub(1));
+        } else {
+            self.done = true;
+        }
+    }
+}
+
+impl<'a, C: Cells> Iterator for Ray<'a, C> {
+    type Item = (C::Pos, &'a C::Cell);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if self.steps_left == Some(0) || self.has_left() {
+                self.finish_direction();
+                continue;
+            }
+            let point = self.point;
+            self.point += self.direction;
+            self.steps_left = self.steps_left.map(|steps| steps - 1);
+
+            let cells = self.cells;
+            let Some((pos, cell)) = cells.locate(point).and_then(|pos| Some((pos, cells.get(pos)?))) else {
+                continue;
+            };
+            if self.stop_before.as_mut().is_some_and(|stop| stop(cell)) {
+                self.finish_direction();
+                continue;
+            }
+            if self.stop_at.as_mut().is_some_and(|stop| stop(cell)) {
+                self.finish_direction();
+            }
+            return Some((pos, cell));
+        }
+        None
+    }
+}
+
+/// The cells from `origin` on in steps of `direction`. The origin may lie outside of the cells,
+/// the ray enters them if it passes through. Ends once it has left the extent of the cells, so
+/// never on a `Torus`, unless limited by the options of `Ray`.
+pub fn cast_ray<C: Cells>(cells: &C, origin: PlaneIdx, direction: TableDir) -> Ray<'_, C> {
+    Ray {
+        cells,
+        origin,
+        point: origin,
+        direction,
+        max_len: None,
+        steps_left: None,
+        stop_before: None,
+        stop_at: None,
+        both_ways: false,
+        done: false,
+    }
+}
+
+/// The points of the Bresenham line from `from` to `to`, both included.
+struct Bresenham {
+    point: PlaneIdx,
+    to: PlaneIdx,
+    step: TableDir,
+    delta: TableDir,
+    error: isize,
+    done: bool,
+}
+
+impl Iterator for Bresenham {
+    type Item = PlaneIdx;
+
+    fn next(&mut self) -> Option<PlaneIdx> {
+        if self.done {
+            return None;
+        }
+        let point = self.point;
+        if point == self.to {
+            self.done = true;
+        } else {
+            let doubled = 2 * self.error;
+            if doubled >= self.delta.y {
+                self.error += self.delta.y;
+                self.point.x += self.step.x;
+            }
+            if doubled <= self.delta.x {
+                self.error += self.delta.x;
+                self.point.y += self.step.y;
+            }
+        }
+        Some(point)
+    }
+}
+
+/// The cells on the straight line from `from` to `to`, both included, approximated with
+/// Bresenham's algorithm where the line isn't horizontal, vertical or diagonal. Points without
+/// a cell are skipped.
+pub fn cast_segment<C: Cells>(
+    cells: &C,
+    from: PlaneIdx,
+    to: PlaneIdx,
+) -> impl Iterator<Item = (C::Pos, &C::Cell)> {
+    let diff = to - from;
+    let delta = TableDir::new(diff.x.abs(), -diff.y.abs());
+    let points = Bresenham {
+        point: from,
+        to,
+        step: diff.map(isize::signum),
+        delta,
+        error: delta.x + delta.y,
+        done: false,
+    };
+    points.filter_map(move |point| {
+        let pos = cells.locate(point)?;
+        Some((pos, cells.get(pos)?))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use ndarray::arr2;
+
+    use crate::table::{CharTable, Grid, SparseGrid, Torus};
+
+    #[fixture]
+    fn table() -> CharTable {
+        arr2(&[['A', 'B', 'C', 'D'], ['E', 'F', 'G', 'H'], ['I', 'J', 'K', 'L']])
+    }
+
+    fn collect<'a>(ray: impl Iterator<Item = (TableIdx, &'a char)>) -> String {
+        ray.map(|(_, c)| *c).collect()
+    }
+
+    #[rstest]
+    #[case(PlaneIdx::new(0, 0), TableDir::new(1, 0), "ABCD")]
+    #[case(PlaneIdx::new(3, 2), TableDir::new(-1, -1), "LGB")]
+    #[case(PlaneIdx::new(-2, 1), TableDir::new(1, 0), "EFGH")]
+    #[case(PlaneIdx::new(-1, -3), TableDir::new(1, 2), "F")]
+    #[case(PlaneIdx::new(-1, 0), TableDir::new(-1, 0), "")]
+    #[case(PlaneIdx::new(5, 5), TableDir::new(0, 0), "")]
+    fn test_rays(table: CharTable, #[case] origin: PlaneIdx, #[case] direction: TableDir, #[case] expected: &str) {
+        assert_eq!(collect(cast_ray(&table, origin, direction)), expected)
+    }
+
+    #[rstest]
+    fn test_ray_options(table: CharTable) {
+        let right = TableDir::new(1, 0);
+        assert_eq!(collect(cast_ray(&table, PlaneIdx::new(0, 1), right).max_len(2)), "EF");
+        assert_eq!(collect(cast_ray(&table, PlaneIdx::new(-1, 1), right).max_len(2)), "E");
+        assert_eq!(collect(cast_ray(&table, PlaneIdx::new(0, 1), right).stop_before(|c| *c == 'G')), "EF");
+        assert_eq!(collect(cast_ray(&table, PlaneIdx::new(0, 1), right).stop_at(|c| *c == 'G')), "EFG");
+        assert_eq!(collect(cast_ray(&table, PlaneIdx::new(1, 1), right).both_ways()), "FGHE");
+        let limited = cast_ray(&table, PlaneIdx::new(2, 1), right).both_ways().max_len(2);
+        assert_eq!(collect(limited), "GHF");
+        let walled = cast_ray(&table, PlaneIdx::new(1, 1), right).both_ways().stop_before(|c| *c == 'H');
+        assert_eq!(collect(walled), "FGE");
+    }
+
+    #[rstest]
+    fn test_rays_elsewhere(table: CharTable) {
+        let torus: Torus<char> = Grid::from(table).try_into().unwrap();
+        let ray: String = cast_ray(&torus, PlaneIdx::new(-1, 0), TableDir::new(1, 0)).take(6).map(|(_, c)| *c).collect();
+        assert_eq!(ray, "DABCDA");
+
+        let sparse: SparseGrid<char> = [(PlaneIdx::new(-4, 0), 'X'), (PlaneIdx::new(4, 0), 'Y')].into_iter().collect();
+        let ray: String = cast_ray(&sparse, PlaneIdx::new(0, 0), TableDir::new(1, 0)).both_ways().map(|(_, c)| *c).collect();
+        assert_eq!(ray, "YX");
+    }
+
+    #[rstest]
+    #[case(PlaneIdx::new(0, 0), PlaneIdx::new(3, 0), "ABCD")]
+    #[case(PlaneIdx::new(3, 2), PlaneIdx::new(0, 2), "LKJI")]
+    #[case(PlaneIdx::new(0, 0), PlaneIdx::new(2, 2), "AFK")]
+    #[case(PlaneIdx::new(0, 0), PlaneIdx::new(3, 1), "ABGH")]
+    #[case(PlaneIdx::new(-1, 0), PlaneIdx::new(1, 2), "EJ")]
+    #[case(PlaneIdx::new(1, 1), PlaneIdx::new(1, 1), "F")]
+    fn test_segments(table: CharTable, #[case] from: PlaneIdx, #[case] to: PlaneIdx, #[case] expected: &str) {
+        assert_eq!(collect(cast_segment(&table, from, to)), expected)
+    }
+}
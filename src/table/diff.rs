@@ -0,0 +1,177 @@
+use super::{Grid, TableIdx};
+
+/// A position whose cell differs between two grids, `None` where it lies outside of a grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Change<'a, T> {
+    pub pos: TableIdx,
+    pub before: Option<&'a T>,
+    pub after: Option<&'a T>,
+}
+
+/// The differences between two grids, see `diff`.
+#[derive(Debug, Clone)]
+pub struct GridDiff<'a, T> {
+    before: &'a Grid<T>,
+    after: &'a Grid<T>,
+    changes: Vec<Change<'a, T>>,
+}
+
+/// Compares two grids cell by cell. Grids of different sizes are compared over the area
+/// covered by either of them.
+pub fn diff<'a, T: PartialEq>(before: &'a Grid<T>, after: &'a Grid<T>) -> GridDiff<'a, T> {
+    let width = before.width().max(after.width());
+    let height = before.height().max(after.height());
+    let changes = (0..height)
+        .flat_map(|y| (0..width).map(move |x| TableIdx::new(x, y)))
+        .map(|pos| Change {
+            pos,
+            before: before.get(pos),
+            after: after.get(pos),
+        })
+        .filter(|change| change.before != change.after)
+        .collect();
+    GridDiff {
+        before,
+        after,
+        changes,
+    }
+}
+
+impl<'a, T> GridDiff<'a, T> {
+    /// The differing positions in row order.
+    pub fn changes(&self) -> &[Change<'a, T>] {
+        &self.changes
+    }
+
+    fn changed(&self, pos: TableIdx) -> bool {
+        self.changes
+            .binary_search_by_key(&(pos.y, pos.x), |c| (c.pos.y, c.pos.x))
+            .is_ok()
+    }
+}
+
+impl<T: Copy + Into<char>> GridDiff<'_, T> {
+    /// Both grids next to each other, rows holding a change are marked with `<`.
+    pub fn side_by_side(&self) -> String {
+        let row = |grid: &Grid<T>, y: usize, width: usize| -> String {
+            (0..width)
+                .map(|x| {
+                    grid.get(TableIdx::new(x, y))
+                        .map_or(' ', |cell| (*cell).into())
+                })
+                .collect()
+        };
+        let left = self.before.width().max("before".len());
+        let right = self.after.width().max("after".len());
+        let mut ret = format!("{:<left$}  after\n", "before");
+        for y in 0..self.before.height().max(self.after.height()) {
+            let marker = if self.changes.iter().any(|c| c.pos.y == y) {
+                "  <"
+            } else {
+                ""
+            };
+            let line = format!(
+                "{}  {}{}",
+                row(self.before, y, left),
+                row(self.after, y, right),
+                marker
+            );
+            ret += line.trim_end();
+            ret.push('\n');
+        }
+        ret
+    }
+
+    /// The new grid with every changed cell shown in reverse video.
+    pub fn highlighted(&self) -> String {
+        let mut ret = String::new();
+        for (y, row) in self.after.rows().enumerate() {
+            for (x, cell) in row.enumerate() {
+                let c: char = (*cell).into();
+                if self.changed(TableIdx::new(x, y)) {
+                    ret += &format!("\x1b[7m{}\x1b[0m", c);
+                } else {
+                    ret.push(c);
+                }
+            }
+            ret.push('\n');
+        }
+        ret
+    }
+}
+
+/// Panics with a side by side view of both grids unless they are equal.
+#[cfg(test)]
+#[track_caller]
+pub fn assert_grid_eq<T: Copy + PartialEq + Into<char>>(actual: &Grid<T>, expected: &Grid<T>) {
+    let diff = diff(actual, expected);
+    if !diff.changes().is_empty() {
+        panic!(
+            "grids differ in {} cells, actual before expected:\n{}",
+            diff.changes().len(),
+            diff.side_by_side()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::parse_char_table;
+
+    fn grid(input: &str) -> Grid<char> {
+        parse_char_table(input).unwrap().into()
+    }
+
+    #[rstest]
+    fn test_changes() {
+        let (before, after) = (grid("#.O\n.@.\n"), grid("#O.\n.@.\n"));
+        let diff = diff(&before, &after);
+        let changes: Vec<(TableIdx, char, char)> = diff
+            .changes()
+            .iter()
+            .map(|c| (c.pos, *c.before.unwrap(), *c.after.unwrap()))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (TableIdx::new(1, 0), '.', 'O'),
+                (TableIdx::new(2, 0), 'O', '.')
+            ]
+        );
+        assert!(super::diff(&before, &before).changes().is_empty());
+    }
+
+    #[rstest]
+    fn test_different_sizes() {
+        let (before, after) = (grid("ab\n"), grid("a\nc\n"));
+        let diff = diff(&before, &after);
+        let positions: Vec<TableIdx> = diff.changes().iter().map(|c| c.pos).collect();
+        assert_eq!(positions, [TableIdx::new(1, 0), TableIdx::new(0, 1)]);
+        assert_eq!(diff.changes()[0].after, None);
+        assert_eq!(
+            diff.side_by_side(),
+            "before  after\nab      a      <\n        c      <\n"
+        );
+    }
+
+    #[rstest]
+    fn test_views() {
+        let (before, after) = (grid("#.O\n.@.\n"), grid("#O.\n.@.\n"));
+        let diff = diff(&before, &after);
+        assert_eq!(
+            diff.side_by_side(),
+            "before  after\n#.O     #O.    <\n.@.     .@.\n"
+        );
+        assert_eq!(diff.highlighted(), "#\x1b[7mO\x1b[0m\x1b[7m.\x1b[0m\n.@.\n");
+    }
+
+    #[rstest]
+    #[should_panic(expected = "grids differ in 1 cells")]
+    fn test_assert() {
+        assert_grid_eq(&grid("ab\n"), &grid("ab\n"));
+        assert_grid_eq(&grid("ab\n"), &grid("aa\n"));
+    }
+}
@@ -0,0 +1,185 @@
+use super::TableDir;
+
+/// One of the four orthogonal directions, with up pointing to lower rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir4 {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Dir4 {
+    /// Clockwise, starting with `Up`.
+    pub const ALL: [Dir4; 4] = [Dir4::Up, Dir4::Right, Dir4::Down, Dir4::Left];
+
+    pub fn turn_right(self) -> Self {
+        Self::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Self {
+        Self::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Self {
+        Self::ALL[(self as usize + 2) % 4]
+    }
+
+    pub fn is_horizontal(self) -> bool {
+        matches!(self, Dir4::Left | Dir4::Right)
+    }
+
+    pub const fn to_vec(self) -> TableDir {
+        match self {
+            Dir4::Up => TableDir::new(0, -1),
+            Dir4::Right => TableDir::new(1, 0),
+            Dir4::Down => TableDir::new(0, 1),
+            Dir4::Left => TableDir::new(-1, 0),
+        }
+    }
+}
+
+impl TryFrom<char> for Dir4 {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Self, char> {
+        match c {
+            '^' => Ok(Dir4::Up),
+            '>' => Ok(Dir4::Right),
+            'v' => Ok(Dir4::Down),
+            '<' => Ok(Dir4::Left),
+            c => Err(c),
+        }
+    }
+}
+
+impl From<Dir4> for char {
+    fn from(dir: Dir4) -> char {
+        match dir {
+            Dir4::Up => '^',
+            Dir4::Right => '>',
+            Dir4::Down => 'v',
+            Dir4::Left => '<',
+        }
+    }
+}
+
+impl From<Dir4> for TableDir {
+    fn from(dir: Dir4) -> Self {
+        dir.to_vec()
+    }
+}
+
+/// One of the four orthogonal or four diagonal directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir8 {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Dir8 {
+    /// Clockwise, starting with `Up`.
+    pub const ALL: [Dir8; 8] = [
+        Dir8::Up,
+        Dir8::UpRight,
+        Dir8::Right,
+        Dir8::DownRight,
+        Dir8::Down,
+        Dir8::DownLeft,
+        Dir8::Left,
+        Dir8::UpLeft,
+    ];
+
+    /// Clockwise, starting with `UpRight`.
+    pub const DIAGONALS: [Dir8; 4] = [Dir8::UpRight, Dir8::DownRight, Dir8::DownLeft, Dir8::UpLeft];
+
+    /// An eighth turn clockwise.
+    pub fn turn_right(self) -> Self {
+        Self::ALL[(self as usize + 1) % 8]
+    }
+
+    /// An eighth turn counterclockwise.
+    pub fn turn_left(self) -> Self {
+        Self::ALL[(self as usize + 7) % 8]
+    }
+
+    pub fn reverse(self) -> Self {
+        Self::ALL[(self as usize + 4) % 8]
+    }
+
+    pub const fn to_vec(self) -> TableDir {
+        match self {
+            Dir8::Up => TableDir::new(0, -1),
+            Dir8::UpRight => TableDir::new(1, -1),
+            Dir8::Right => TableDir::new(1, 0),
+            Dir8::DownRight => TableDir::new(1, 1),
+            Dir8::Down => TableDir::new(0, 1),
+            Dir8::DownLeft => TableDir::new(-1, 1),
+            Dir8::Left => TableDir::new(-1, 0),
+            Dir8::UpLeft => TableDir::new(-1, -1),
+        }
+    }
+}
+
+impl From<Dir8> for TableDir {
+    fn from(dir: Dir8) -> Self {
+        dir.to_vec()
+    }
+}
+
+impl From<Dir4> for Dir8 {
+    fn from(dir: Dir4) -> Self {
+        match dir {
+            Dir4::Up => Dir8::Up,
+            Dir4::Right => Dir8::Right,
+            Dir4::Down => Dir8::Down,
+            Dir4::Left => Dir8::Left,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    fn turn_dir4() {
+        for dir in Dir4::ALL {
+            assert_eq!(dir.turn_right().turn_left(), dir);
+            assert_eq!(dir.turn_right().turn_right(), dir.reverse());
+            assert_eq!(dir.reverse().to_vec(), -dir.to_vec());
+            assert_eq!(Dir4::try_from(char::from(dir)), Ok(dir));
+            assert_eq!(Dir8::from(dir).to_vec(), dir.to_vec());
+        }
+        assert_eq!(Dir4::Up.turn_right(), Dir4::Right);
+        assert_eq!(Dir4::Up.turn_left(), Dir4::Left);
+        assert_eq!(Dir4::try_from('x'), Err('x'));
+    }
+
+    #[rstest]
+    fn reverse_dir8() {
+        for dir in Dir8::ALL {
+            assert_eq!(dir.reverse().to_vec(), -dir.to_vec());
+        }
+        assert!(Dir8::DIAGONALS.iter().all(|dir| dir.to_vec().x != 0 && dir.to_vec().y != 0));
+    }
+
+    #[rstest]
+    fn turn_dir8() {
+        for dir in Dir8::ALL {
+            assert_eq!(dir.turn_right().turn_left(), dir);
+            assert_eq!((0..4).fold(dir, |d, _| d.turn_right()), dir.reverse());
+        }
+        assert_eq!(Dir8::from(Dir4::Up).turn_right().turn_right(), Dir8::from(Dir4::Right));
+        assert_eq!(Dir8::Up.turn_right(), Dir8::UpRight);
+        assert_eq!(Dir8::Up.turn_left(), Dir8::UpLeft);
+        assert_eq!(Dir8::UpLeft.turn_right(), Dir8::Up);
+    }
+}
@@ -0,0 +1,220 @@
+use std::ops::{Index, IndexMut};
+
+use ndarray::Array2;
+
+use crate::prelude::ToPuzzleString;
+
+use super::{from_pattern, into_shape, shift_within, Cells, CharTable, PlaneIdx, TableDir, TableIdx};
+
+/// A two dimensional grid of cells addressed by (x, y) positions.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Array2<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn width(&self) -> usize {
+        self.cells.ncols()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.nrows()
+    }
+
+    pub fn contains(&self, idx: TableIdx) -> bool {
+        idx.x < self.width() && idx.y < self.height()
+    }
+
+    /// Moves `idx` by `step`, or `None` if that leaves the grid.
+    pub fn shift(&self, idx: TableIdx, step: TableDir) -> Option<TableIdx> {
+        shift_within(idx, step, self.cells.dim())
+    }
+
+    pub fn get(&self, idx: TableIdx) -> Option<&T> {
+        self.cells.get(into_shape(idx))
+    }
+
+    pub fn get_mut(&mut self, idx: TableIdx) -> Option<&mut T> {
+        self.cells.get_mut(into_shape(idx))
+    }
+
+    /// All cells with their position, row by row.
+    pub fn indexed_iter(&self) -> impl Iterator<Item = (TableIdx, &T)> + '_ {
+        self.cells
+            .indexed_iter()
+            .map(|(pattern, cell)| (from_pattern(pattern), cell))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> + '_ {
+        self.cells.rows().into_iter().map(|row| row.into_iter())
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> + '_ {
+        self.cells.columns().into_iter().map(|column| column.into_iter())
+    }
+
+    /// Position of the first cell, row by row, that satisfies the predicate.
+    pub fn find(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<TableIdx> {
+        self.indexed_iter()
+            .find(|(_, cell)| predicate(cell))
+            .map(|(idx, _)| idx)
+    }
+
+    pub fn find_all<'a>(
+        &'a self,
+        mut predicate: impl FnMut(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = TableIdx> + 'a {
+        self.indexed_iter()
+            .filter(move |(_, cell)| predicate(cell))
+            .map(|(idx, _)| idx)
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.map(f),
+        }
+    }
+
+    /// Like `map`, but stops at the first cell that can't be converted.
+    pub fn try_map<U, E>(&self, f: impl FnMut(&T) -> Result<U, E>) -> Result<Grid<U>, E> {
+        let cells = self.cells.iter().map(f).collect::<Result<Vec<U>, E>>()?;
+        Ok(Grid {
+            cells: Array2::from_shape_vec(self.cells.dim(), cells)
+                .expect("mapping keeps the shape"),
+        })
+    }
+
+    pub fn as_array(&self) -> &Array2<T> {
+        &self.cells
+    }
+}
+
+impl<T> Cells for Grid<T> {
+    type Pos = TableIdx;
+    type Cell = T;
+
+    fn shift(&self, pos: TableIdx, step: TableDir) -> Option<TableIdx> {
+        Grid::shift(self, pos, step)
+    }
+
+    fn get(&self, pos: TableIdx) -> Option<&T> {
+        Grid::get(self, pos)
+    }
+
+    fn locate(&self, point: PlaneIdx) -> Option<TableIdx> {
+        self.cells.locate(point)
+    }
+
+    fn extent(&self) -> Option<(PlaneIdx, PlaneIdx)> {
+        self.cells.extent()
+    }
+}
+
+impl<T> From<Array2<T>> for Grid<T> {
+    fn from(cells: Array2<T>) -> Self {
+        Self { cells }
+    }
+}
+
+impl<T> Index<TableIdx> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, idx: TableIdx) -> &T {
+        &self.cells[into_shape(idx)]
+    }
+}
+
+impl<T> IndexMut<TableIdx> for Grid<T> {
+    fn index_mut(&mut self, idx: TableIdx) -> &mut T {
+        &mut self.cells[into_shape(idx)]
+    }
+}
+
+impl<T: Copy + Into<char>> Grid<T> {
+    /// The grid with every cell replaced by the symbol it was parsed from.
+    pub fn to_chars(&self) -> Grid<char> {
+        self.map(|cell| (*cell).into())
+    }
+}
+
+impl<T: Copy + Into<char>> ToPuzzleString for Grid<T> {
+    fn to_puzzle_string(&self) -> String {
+        self.to_chars().cells.to_puzzle_string()
+    }
+}
+
+impl From<Grid<char>> for CharTable {
+    fn from(grid: Grid<char>) -> Self {
+        grid.cells
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use ndarray::arr2;
+
+    use crate::table::Dir4;
+
+    #[fixture]
+    fn grid() -> Grid<char> {
+        arr2(&[['A', 'B', 'C'], ['D', 'E', 'A']]).into()
+    }
+
+    #[rstest]
+    #[case(TableIdx::new(2, 0), Some(&'C'))]
+    #[case(TableIdx::new(0, 1), Some(&'D'))]
+    #[case(TableIdx::new(3, 0), None)]
+    #[case(TableIdx::new(0, 2), None)]
+    fn test_get(grid: Grid<char>, #[case] idx: TableIdx, #[case] expected: Option<&char>) {
+        assert_eq!(grid.get(idx), expected)
+    }
+
+    #[rstest]
+    fn test_find(grid: Grid<char>) {
+        assert_eq!(grid.find(|c| *c == 'A'), Some(TableIdx::new(0, 0)));
+        assert_eq!(grid.find(|c| *c == 'Z'), None);
+        assert_eq!(
+            grid.find_all(|c| *c == 'A').collect::<Vec<_>>(),
+            [TableIdx::new(0, 0), TableIdx::new(2, 1)]
+        );
+    }
+
+    #[rstest]
+    fn test_rows_and_columns(grid: Grid<char>) {
+        let rows: Vec<String> = grid.rows().map(|r| r.collect()).collect();
+        let columns: Vec<String> = grid.columns().map(|c| c.collect()).collect();
+        assert_eq!(rows, ["ABC", "DEA"]);
+        assert_eq!(columns, ["AD", "BE", "CA"]);
+    }
+
+    #[rstest]
+    fn test_neighbors(grid: Grid<char>) {
+        let corner: String = grid.neighbors4(TableIdx::new(0, 0)).map(|(_, c)| c).collect();
+        assert_eq!(corner, "BD");
+        let center: String = grid.neighbors8(TableIdx::new(1, 0)).map(|(_, c)| c).collect();
+        assert_eq!(center, "CAEDA");
+        let knight: Vec<TableIdx> = grid
+            .neighbors_with(TableIdx::new(0, 0), [TableDir::new(2, 1), TableDir::new(1, 2)])
+            .map(|(pos, _)| pos)
+            .collect();
+        assert_eq!(knight, [TableIdx::new(2, 1)]);
+        let dirs: Vec<Dir4> = grid
+            .neighbors4(TableIdx::new(2, 1))
+            .with_dirs()
+            .map(|(dir, _, _)| dir)
+            .collect();
+        assert_eq!(dirs, [Dir4::Up, Dir4::Left]);
+    }
+
+    #[rstest]
+    fn test_map(grid: Grid<char>) {
+        let mapped = grid.map(|c| *c == 'A');
+        assert!(mapped[TableIdx::new(2, 1)]);
+        assert!(!mapped[TableIdx::new(1, 1)]);
+        assert!(grid.try_map(|c| c.to_digit(16).ok_or(*c)).is_ok());
+        assert_eq!(grid.try_map(|c| c.to_digit(10).ok_or(*c)), Err('A'));
+    }
+}
@@ -1,26 +1,115 @@
+use std::borrow::Cow;
+
+use crate::prelude::ToPuzzleString;
+
 use ndarray::{Array2, Ix2};
 
 use vek::vec::repr_c::Vec2;
 
+mod cells;
+mod diff;
+mod dir;
+mod grid;
+mod regions;
+mod record;
+mod render;
+pub mod search;
+mod sparse;
+mod torus;
+mod transform;
+
+pub use cells::{cast_ray, cast_segment, Cells};
+#[cfg(test)]
+pub use diff::assert_grid_eq;
+pub use diff::diff;
+pub use dir::{Dir4, Dir8};
+pub use grid::Grid;
+pub use regions::{regions, Regions};
+pub use record::Recorder;
+pub use render::{Canvas, Rgb};
+pub use sparse::SparseGrid;
+pub use torus::Torus;
+pub use transform::Symmetry;
+
 pub type CharTable = Array2<char>;
 
 pub type TableIdx = Vec2<usize>;
 
 pub type TableDir = Vec2<isize>;
 
+/// A position on an unbounded grid, see `SparseGrid`.
+pub type PlaneIdx = Vec2<isize>;
+
+/// How `parse_char_table_with` treats lines that don't match the width of the table.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableOptions {
+    /// Drop whitespace at the end of every line before measuring it.
+    pub trim_trailing_whitespace: bool,
+    /// Fill rows shorter than the longest one with this character instead of failing.
+    pub pad: Option<char>,
+}
+
 pub fn parse_char_table(input: &str) -> anyhow::Result<CharTable> {
-    let mut height = 0;
-    let mut width = 0;
-    let mut arr = Vec::new();
-
-    for line in input.lines() {
-        height += 1;
-        if width == 0 {
-            width = line.len();
+    parse_char_table_with(input, TableOptions::default())
+}
+
+pub fn parse_char_table_with(input: &str, options: TableOptions) -> anyhow::Result<CharTable> {
+    let lines: Vec<Vec<char>> = input
+        .lines()
+        .map(|line| {
+            if options.trim_trailing_whitespace {
+                line.trim_end().chars().collect()
+            } else {
+                line.chars().collect()
+            }
+        })
+        .collect();
+
+    let width = match options.pad {
+        Some(_) => lines.iter().map(Vec::len).max().unwrap_or(0),
+        None => lines.first().map_or(0, Vec::len),
+    };
+    let mut arr = Vec::with_capacity(width * lines.len());
+
+    for (n, line) in lines.iter().enumerate() {
+        match options.pad {
+            Some(pad) => {
+                arr.extend(line);
+                arr.extend(std::iter::repeat_n(pad, width - line.len()));
+            }
+            None if line.len() != width => {
+                return Err(anyhow::anyhow!(
+                    "line {} has {} characters, expected {} like the first line",
+                    n + 1,
+                    line.len(),
+                    width
+                ));
+            }
+            None => arr.extend(line),
         }
-        arr.extend(line.chars());
     }
-    Ok(CharTable::from_shape_vec((height, width), arr)?)
+    Ok(CharTable::from_shape_vec((lines.len(), width), arr)?)
+}
+
+/// Parses a table whose cells are typed symbols, failing on the first character that isn't one.
+pub fn parse_grid<T: TryFrom<char>>(input: &str) -> anyhow::Result<Grid<T>> {
+    let table: Grid<char> = parse_char_table(input)?.into();
+    table.try_map(|c| T::try_from(*c).map_err(|_| *c)).map_err(|c| {
+        // The first cell holding the symbol is the one the conversion stopped at.
+        let (idx, _) = table.indexed_iter().find(|(_, cell)| **cell == c).expect("the symbol is in the table");
+        anyhow::anyhow!("unknown symbol {:?} at row {}, column {}", c, idx.y + 1, idx.x + 1)
+    })
+}
+
+impl ToPuzzleString for CharTable {
+    fn to_puzzle_string(&self) -> String {
+        let mut ret = String::new();
+        for row in self.rows() {
+            ret.extend(row);
+            ret.push('\n');
+        }
+        ret
+    }
 }
 
 pub fn into_shape(idx: TableIdx) -> Ix2 {
@@ -39,43 +128,20 @@ pub fn from_pattern(p: (usize, usize)) -> TableIdx {
     into_idx(Ix2(p.0, p.1))
 }
 
-pub fn shift(idx: TableIdx, step: TableDir) -> TableIdx {
-    (idx.as_::<isize>() + step).as_()
-}
-
-#[derive(Debug)]
-pub struct Ray<'a, A> {
-    table: &'a Array2<A>,
-    coord: TableIdx,
-    direction: TableDir,
-}
-
-impl <'a, A> Iterator for Ray<'a, A> {
-    type Item = (TableIdx, &'a A);
-
-    fn next(&mut self) -> Option<Self::Item> {
-
-        let cur= self.table.get(into_shape(self.coord))?;
-
-        let retpos = self.coord;
-
-        self.coord = shift(self.coord, self.direction);
-
-        Some((retpos, cur))
-
-
-    }
+/// Moves `idx` by `step`, or `None` if that would leave the non-negative quadrant.
+pub fn checked_shift(idx: TableIdx, step: TableDir) -> Option<TableIdx> {
+    Some(TableIdx::new(
+        idx.x.checked_add_signed(step.x)?,
+        idx.y.checked_add_signed(step.y)?,
+    ))
 }
 
-pub fn cast_ray<A>(table: &Array2<A>, origin: TableIdx, direction: TableDir) -> Ray<A> {
-    Ray {
-        table,
-        coord: origin,
-        direction,
-    }
+/// Like `checked_shift`, but also `None` past a table of the given `(rows, columns)` shape.
+pub fn shift_within(idx: TableIdx, step: TableDir, shape: (usize, usize)) -> Option<TableIdx> {
+    checked_shift(idx, step).filter(|next| next.y < shape.0 && next.x < shape.1)
 }
 
-pub struct PPCharTable<'a>(&'a CharTable);
+pub struct PPCharTable<'a>(Cow<'a, CharTable>);
 
 impl std::fmt::Debug for PPCharTable<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -91,7 +157,26 @@ impl std::fmt::Debug for PPCharTable<'_> {
 
 impl <'a>From<&'a CharTable> for PPCharTable<'a> {
     fn from(table: &'a CharTable) -> Self {
-        Self(table)
+        Self(Cow::Borrowed(table))
+    }
+}
+
+impl <'a>From<&'a Grid<char>> for PPCharTable<'a> {
+    fn from(grid: &'a Grid<char>) -> Self {
+        Self(Cow::Borrowed(grid.as_array()))
+    }
+}
+
+impl<'a, T: Copy + Into<char>> From<&'a Torus<T>> for PPCharTable<'a> {
+    fn from(torus: &'a Torus<T>) -> Self {
+        Self(Cow::Owned(torus.as_grid().to_chars().into()))
+    }
+}
+
+/// Renders the area holding all cells, with `.` where no cell was set.
+impl From<&SparseGrid<char>> for PPCharTable<'_> {
+    fn from(sparse: &SparseGrid<char>) -> Self {
+        Self(Cow::Owned(sparse.to_grid('.').0.into()))
     }
 }
  
@@ -113,12 +198,83 @@ mod tests {
 
     }
 
+    #[rstest]
+    fn test_ragged_lines() {
+        let err = parse_char_table("ABC\nDE\nFGHI\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2 has 2 characters, expected 3 like the first line");
+        assert!(parse_char_table("ABC \nDEF\n").is_err());
+    }
+
+    #[rstest]
+    fn test_table_options(rect_table: CharTable) {
+        let trim = TableOptions {
+            trim_trailing_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(parse_char_table_with("ABC  \nDEF\t\n", trim).unwrap(), rect_table);
+
+        let pad = TableOptions {
+            pad: Some('.'),
+            ..Default::default()
+        };
+        assert_eq!(
+            parse_char_table_with("AB\nDEF\n", pad).unwrap(),
+            arr2(&[['A', 'B', '.'], ['D', 'E', 'F']])
+        );
+    }
+
+    #[rstest]
+    #[case(TableIdx::new(1, 1), TableDir::new(-1, 1), Some(TableIdx::new(0, 2)))]
+    #[case(TableIdx::new(0, 1), TableDir::new(-1, 0), None)]
+    #[case(TableIdx::new(2, 0), TableDir::new(0, -1), None)]
+    #[case(TableIdx::new(2, 1), TableDir::new(1, 0), Some(TableIdx::new(3, 1)))]
+    fn test_checked_shift(#[case] idx: TableIdx, #[case] step: TableDir, #[case] expected: Option<TableIdx>) {
+        assert_eq!(checked_shift(idx, step), expected)
+    }
+
+    #[rstest]
+    fn test_shift_within(rect_table: CharTable) {
+        let shape = rect_table.dim();
+        assert_eq!(shift_within(TableIdx::new(1, 0), TableDir::new(1, 1), shape), Some(TableIdx::new(2, 1)));
+        assert_eq!(shift_within(TableIdx::new(2, 1), TableDir::new(1, 0), shape), None);
+        assert_eq!(shift_within(TableIdx::new(2, 1), TableDir::new(0, 1), shape), None);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Bit {
+        Zero,
+        One,
+    }
+
+    impl TryFrom<char> for Bit {
+        type Error = char;
+
+        fn try_from(c: char) -> Result<Self, char> {
+            match c {
+                '0' => Ok(Bit::Zero),
+                '1' => Ok(Bit::One),
+                c => Err(c),
+            }
+        }
+    }
+
+    #[rstest]
+    fn test_parse_grid() {
+        let grid: Grid<Bit> = parse_grid("01\n10\n").unwrap();
+        assert_eq!(grid[TableIdx::new(1, 0)], Bit::One);
+        assert_eq!(grid[TableIdx::new(1, 1)], Bit::Zero);
+
+        let err = parse_grid::<Bit>("01\n1x\n").unwrap_err();
+        assert_eq!(err.to_string(), "unknown symbol 'x' at row 2, column 2");
+    }
+
     #[rstest]
     #[case(TableIdx::new(0, 0), TableDir::new(0, 1), "AD")]
     #[case(TableIdx::new(0, 0), TableDir::new(1, 1), "AE")]
     #[case(TableIdx::new(0, 0), TableDir::new(1, 0), "ABC")]
+    #[case(TableIdx::new(2, 1), TableDir::new(-1, -1), "FB")]
     fn test_rays(rect_table: CharTable, #[case] origin: TableIdx, #[case] direction: TableDir, #[case] expected: &str) {
-        let ray: String = cast_ray(&rect_table, origin, direction).map(|(_, c)| *c).collect();
+        let ray: String = cast_ray(&rect_table, origin.as_(), direction).map(|(_, c)| *c).collect();
 
         assert_eq!(&ray, expected)
 
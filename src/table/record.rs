@@ -0,0 +1,309 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use crate::prelude::json_string;
+
+use super::{Canvas, Grid, Rgb};
+
+/// Collects snapshots of a grid while a simulation runs. Only every `every`th snapshot is
+/// kept and runs of equal frames are collapsed into one.
+#[derive(Debug, Clone)]
+pub struct Recorder<T> {
+    frames: Vec<Grid<T>>,
+    every: usize,
+    pushed: usize,
+    /// The latest frame left out by sampling, so the recording still ends on the final state.
+    skipped: Option<Grid<T>>,
+}
+
+impl<T> Default for Recorder<T> {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            every: 1,
+            pushed: 0,
+            skipped: None,
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> Recorder<T> {
+    /// Keeps only every `n`th frame pushed, starting with the first.
+    pub fn every(self, n: usize) -> Self {
+        Self {
+            every: n.max(1),
+            ..self
+        }
+    }
+
+    /// Fails if the frame is shaped differently from the first one.
+    pub fn push(&mut self, frame: &Grid<T>) -> anyhow::Result<()> {
+        if let Some(first) = self.frames.first() {
+            if (first.width(), first.height()) != (frame.width(), frame.height()) {
+                return Err(anyhow!(
+                    "frame is {}x{}, but the recording is {}x{}",
+                    frame.width(),
+                    frame.height(),
+                    first.width(),
+                    first.height()
+                ));
+            }
+        }
+        let sampled = self.pushed.is_multiple_of(self.every);
+        self.pushed += 1;
+        if sampled {
+            self.skipped = None;
+            self.keep(frame.clone());
+        } else {
+            self.skipped = Some(frame.clone());
+        }
+        Ok(())
+    }
+
+    fn keep(&mut self, frame: Grid<T>) {
+        if self.frames.last() != Some(&frame) {
+            self.frames.push(frame);
+        }
+    }
+
+    pub fn finish(mut self) -> Recording<T> {
+        if let Some(frame) = self.skipped.take() {
+            self.keep(frame);
+        }
+        Recording {
+            frames: self.frames,
+        }
+    }
+}
+
+/// The frames kept by a `Recorder`, ready to be exported as an animation.
+#[derive(Debug, Clone)]
+pub struct Recording<T> {
+    frames: Vec<Grid<T>>,
+}
+
+impl<T> Recording<T> {
+    pub fn frames(&self) -> &[Grid<T>] {
+        &self.frames
+    }
+
+    /// Width and height of the first frame.
+    fn size(&self) -> anyhow::Result<(usize, usize)> {
+        let first = self.frames.first().ok_or(anyhow!("nothing was recorded"))?;
+        Ok((first.width(), first.height()))
+    }
+
+    /// Writes an asciicast v2 terminal recording, every frame drawing one character per cell
+    /// over the previous one.
+    pub fn write_asciicast(
+        &self,
+        mut out: impl Write,
+        fps: u16,
+        mut symbol: impl FnMut(&T) -> char,
+    ) -> anyhow::Result<()> {
+        let (width, height) = self.size()?;
+        writeln!(
+            out,
+            r#"{{"version": 2, "width": {}, "height": {}}}"#,
+            width, height
+        )?;
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let rows: Vec<String> = frame
+                .rows()
+                .map(|row| row.map(&mut symbol).collect())
+                .collect();
+            let screen = format!("\x1b[H\x1b[2J{}", rows.join("\r\n"));
+            let time = idx as f64 / fps.max(1) as f64;
+            writeln!(out, r#"[{:.3}, "o", {}]"#, time, json_string(&screen))?;
+        }
+        Ok(())
+    }
+
+    /// Writes an endlessly looping animated GIF, colored through the palette with every cell
+    /// drawn as a square of `scale` pixels.
+    pub fn write_gif(
+        &self,
+        out: impl Write,
+        fps: u16,
+        scale: usize,
+        mut palette: impl FnMut(&T) -> Rgb,
+    ) -> anyhow::Result<()> {
+        let (width, height) = self.size()?;
+        let too_large = |_| {
+            anyhow!(
+                "{}x{} cells don't fit into a GIF at scale {}",
+                width,
+                height,
+                scale
+            )
+        };
+        let width = u16::try_from(width * scale).map_err(too_large)?;
+        let height = u16::try_from(height * scale).map_err(too_large)?;
+        let mut encoder = gif::Encoder::new(out, width, height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+        for frame in &self.frames {
+            let (_, _, bytes) = Canvas::new(frame, &mut palette).rgb_bytes(scale);
+            let mut frame = gif::Frame::from_rgb_speed(width, height, &bytes, 10);
+            frame.delay = 100 / fps.max(1);
+            encoder.write_frame(&frame)?;
+        }
+        encoder.into_inner()?;
+        Ok(())
+    }
+
+    /// Writes every frame to a numbered PNG file in `dir`, which is created if needed.
+    pub fn save_pngs(
+        &self,
+        dir: &Path,
+        scale: usize,
+        mut palette: impl FnMut(&T) -> Rgb,
+    ) -> anyhow::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (idx, frame) in self.frames.iter().enumerate() {
+            let out = BufWriter::new(File::create(dir.join(format!("frame_{:05}.png", idx)))?);
+            Canvas::new(frame, &mut palette).write_png(out, scale)?;
+        }
+        Ok(())
+    }
+
+    /// Exports to `path` depending on its extension: asciicast for `.cast`, GIF for `.gif`
+    /// and a PNG sequence for a path without extension.
+    pub fn save(
+        &self,
+        path: impl AsRef<Path>,
+        fps: u16,
+        scale: usize,
+        palette: impl FnMut(&T) -> Rgb,
+        symbol: impl FnMut(&T) -> char,
+    ) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("cast") => self.write_asciicast(BufWriter::new(File::create(path)?), fps, symbol),
+            Some("gif") => self.write_gif(BufWriter::new(File::create(path)?), fps, scale, palette),
+            None => self.save_pngs(path, scale, palette),
+            Some(_) => Err(anyhow!(
+                "{} is neither a .cast nor a .gif file or a directory",
+                path.display()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use ndarray::arr2;
+
+    fn frame(cells: [char; 2]) -> Grid<char> {
+        arr2(&[cells]).into()
+    }
+
+    #[fixture]
+    fn recording() -> Recording<char> {
+        let mut recorder = Recorder::default();
+        for cells in [['a', 'b'], ['a', 'b'], ['b', 'a'], ['a', 'b']] {
+            recorder.push(&frame(cells)).unwrap();
+        }
+        recorder.finish()
+    }
+
+    fn palette(c: &char) -> Rgb {
+        if *c == 'a' {
+            Rgb::WHITE
+        } else {
+            Rgb::BLACK
+        }
+    }
+
+    #[rstest]
+    fn test_dedup(recording: Recording<char>) {
+        assert_eq!(
+            recording.frames(),
+            [frame(['a', 'b']), frame(['b', 'a']), frame(['a', 'b'])]
+        );
+    }
+
+    #[rstest]
+    fn test_sampling() {
+        let mut recorder = Recorder::default().every(3);
+        for n in 0..8 {
+            recorder.push(&arr2(&[[n]]).into()).unwrap();
+        }
+        let kept: Vec<i32> = recorder
+            .finish()
+            .frames()
+            .iter()
+            .map(|f| f[(0, 0).into()])
+            .collect();
+        assert_eq!(kept, [0, 3, 6, 7]);
+    }
+
+    #[rstest]
+    fn test_reject_other_shapes() {
+        let mut recorder = Recorder::default();
+        recorder.push(&frame(['a', 'b'])).unwrap();
+        let err = recorder.push(&arr2(&[['a'], ['b']]).into()).unwrap_err();
+        assert_eq!(err.to_string(), "frame is 1x2, but the recording is 2x1");
+    }
+
+    #[rstest]
+    fn test_asciicast(recording: Recording<char>) {
+        let mut out = Vec::new();
+        recording
+            .write_asciicast(&mut out, 4, |c| c.to_ascii_uppercase())
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], r#"{"version": 2, "width": 2, "height": 1}"#);
+        assert_eq!(lines[2], r#"[0.250, "o", "\u001b[H\u001b[2JBA"]"#);
+        assert_eq!(lines.len(), 4);
+
+        let mut out = Vec::new();
+        recording.write_asciicast(&mut out, 0, |c| *c).unwrap();
+        assert!(String::from_utf8(out).unwrap().lines().nth(3).unwrap().starts_with("[2.000, "));
+    }
+
+    #[rstest]
+    fn test_gif(recording: Recording<char>) {
+        let mut out = Vec::new();
+        recording.write_gif(&mut out, 10, 2, palette).unwrap();
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(out.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (4, 2));
+        let mut frames = 0;
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 10);
+            frames += 1;
+        }
+        assert_eq!(frames, 3);
+    }
+
+    #[rstest]
+    fn test_png_sequence(recording: Recording<char>) {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("frames");
+        recording.save(&dir, 10, 1, palette, |c| *c).unwrap();
+        assert!(dir.join("frame_00002.png").exists());
+        let err = recording
+            .save("run.mp4", 10, 1, palette, |c| *c)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "run.mp4 is neither a .cast nor a .gif file or a directory"
+        );
+        let empty = Recorder::<char>::default().finish();
+        assert_eq!(
+            empty
+                .write_gif(Vec::new(), 10, 1, palette)
+                .unwrap_err()
+                .to_string(),
+            "nothing was recorded"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use ndarray::Array2;
+
+use super::{Cells, Dir4, Dir8, Grid, TableDir, TableIdx};
+
+/// A connected area of equal cells, see `regions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub cells: Vec<TableIdx>,
+    /// Number of cell edges between the region and anything else.
+    pub perimeter: usize,
+    /// Number of straight fence segments around the region, holes included. Equals the
+    /// number of corners.
+    pub sides: usize,
+    /// Smallest and largest corner of the bounding box, both inclusive.
+    pub bbox: (TableIdx, TableIdx),
+    /// Number of areas enclosed by the region that don't belong to it.
+    pub holes: usize,
+}
+
+impl Region {
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+/// The regions of a grid together with a grid holding the region label of every cell.
+#[derive(Debug, Clone)]
+pub struct Regions {
+    labels: Grid<usize>,
+    regions: Vec<Region>,
+}
+
+impl Regions {
+    /// Label of the region containing `pos`.
+    pub fn label(&self, pos: TableIdx) -> Option<usize> {
+        self.labels.get(pos).copied()
+    }
+
+    /// The region label of every cell.
+    pub fn labels(&self) -> &Grid<usize> {
+        &self.labels
+    }
+
+    pub fn get(&self, label: usize) -> Option<&Region> {
+        self.regions.get(label)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Region> {
+        self.regions.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+}
+
+/// Collects the cells connected to `start` through the stencil whose values satisfy `member`
+/// and marks them in `seen`.
+fn flood_fill<T, S>(
+    grid: &Grid<T>,
+    start: TableIdx,
+    stencil: S,
+    mut member: impl FnMut(&T) -> bool,
+    seen: &mut Grid<bool>,
+) -> Vec<TableIdx>
+where
+    S: IntoIterator + Clone,
+    S::Item: Copy + Into<TableDir>,
+{
+    let mut cells = Vec::new();
+    let mut todo = vec![start];
+    seen[start] = true;
+    while let Some(pos) = todo.pop() {
+        cells.push(pos);
+        for (next, cell) in grid.neighbors_with(pos, stencil.clone()) {
+            if !seen[next] && member(cell) {
+                seen[next] = true;
+                todo.push(next);
+            }
+        }
+    }
+    cells
+}
+
+/// Splits the grid into 4-connected regions of equal cells, labeled in row order of their
+/// first cell.
+pub fn regions<T: PartialEq>(grid: &Grid<T>) -> Regions {
+    let mut seen = grid.map(|_| false);
+    let mut labels = grid.map(|_| 0);
+    let mut cells = Vec::new();
+    for (pos, cell) in grid.indexed_iter() {
+        if !seen[pos] {
+            let region = flood_fill(grid, pos, Dir4::ALL, |other| other == cell, &mut seen);
+            for member in &region {
+                labels[*member] = cells.len();
+            }
+            cells.push(region);
+        }
+    }
+
+    let regions = cells
+        .into_iter()
+        .enumerate()
+        .map(|(label, cells)| measure(&labels, label, cells))
+        .collect();
+    Regions { labels, regions }
+}
+
+fn measure(labels: &Grid<usize>, label: usize, cells: Vec<TableIdx>) -> Region {
+    let mut perimeter = 0;
+    let mut sides = 0;
+    for pos in &cells {
+        perimeter += 4 - labels.neighbors4(*pos).filter(|(_, other)| **other == label).count();
+
+        let mut inside = [false; 8];
+        for (dir, _, _) in labels.neighbors8(*pos).with_dirs().filter(|(_, _, other)| **other == label) {
+            inside[dir as usize] = true;
+        }
+        for corner in Dir8::DIAGONALS {
+            // The orthogonal neighbors on either side of the corner.
+            let (a, b) = (inside[corner.turn_left() as usize], inside[corner.turn_right() as usize]);
+            // Either both edges around this corner of the cell are fenced, or the corner is
+            // concave.
+            if (!a && !b) || (a && b && !inside[corner as usize]) {
+                sides += 1;
+            }
+        }
+    }
+
+    let min = cells.iter().copied().reduce(|a, b| TableIdx::new(a.x.min(b.x), a.y.min(b.y))).unwrap();
+    let max = cells.iter().copied().reduce(|a, b| TableIdx::new(a.x.max(b.x), a.y.max(b.y))).unwrap();
+    let holes = count_holes(labels, label, (min, max));
+
+    Region {
+        cells,
+        perimeter,
+        sides,
+        bbox: (min, max),
+        holes,
+    }
+}
+
+/// Counts the 8-connected areas outside the region that can't reach the border of its
+/// bounding box grown by one cell.
+fn count_holes(labels: &Grid<usize>, label: usize, (min, max): (TableIdx, TableIdx)) -> usize {
+    let (width, height) = (max.x - min.x + 3, max.y - min.y + 3);
+    let outside: Grid<bool> = Array2::from_shape_fn((height, width), |(y, x)| {
+        let pos = TableIdx::new((min.x + x).wrapping_sub(1), (min.y + y).wrapping_sub(1));
+        labels.get(pos) != Some(&label)
+    })
+    .into();
+
+    let mut seen = outside.map(|_| false);
+    flood_fill(&outside, TableIdx::new(0, 0), Dir8::ALL, |o| *o, &mut seen);
+    let mut holes = 0;
+    for (pos, o) in outside.indexed_iter() {
+        if *o && !seen[pos] {
+            flood_fill(&outside, pos, Dir8::ALL, |o| *o, &mut seen);
+            holes += 1;
+        }
+    }
+    holes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::parse_char_table;
+
+    fn grid(input: &str) -> Grid<char> {
+        parse_char_table(input).unwrap().into()
+    }
+
+    #[rstest]
+    fn label_regions() {
+        let regions = regions(&grid("AAAA\nBBCD\nBBCC\nEEEC\n"));
+        assert_eq!(regions.len(), 5);
+        assert_eq!(regions.label(TableIdx::new(3, 2)), regions.label(TableIdx::new(2, 1)));
+        assert_eq!(regions.label(TableIdx::new(3, 1)), Some(3));
+
+        let c = regions.get(2).unwrap();
+        assert_eq!((c.area(), c.perimeter, c.sides, c.holes), (4, 10, 8, 0));
+        assert_eq!(c.bbox, (TableIdx::new(2, 1), TableIdx::new(3, 3)));
+    }
+
+    #[rstest]
+    #[case("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO\n", 21, 36, 20, 4)]
+    #[case("EEEEE\nEXXXX\nEEEEE\nEXXXX\nEEEEE\n", 17, 36, 12, 0)]
+    #[case("AAA\nA.A\nAAA\n", 8, 16, 8, 1)]
+    #[case("AAA\nA.A\nAA.\n", 7, 16, 10, 0)]
+    fn measure_first_region(
+        #[case] input: &str,
+        #[case] area: usize,
+        #[case] perimeter: usize,
+        #[case] sides: usize,
+        #[case] holes: usize,
+    ) {
+        let region = regions(&grid(input)).get(0).unwrap().clone();
+        assert_eq!((region.area(), region.perimeter, region.sides, region.holes), (area, perimeter, sides, holes));
+    }
+}
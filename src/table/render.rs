@@ -0,0 +1,219 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use super::{Grid, TableIdx};
+
+/// A color with 8 bits per channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+    pub const GRAY: Rgb = Rgb::new(128, 128, 128);
+    pub const RED: Rgb = Rgb::new(220, 50, 47);
+    pub const GREEN: Rgb = Rgb::new(133, 153, 0);
+    pub const YELLOW: Rgb = Rgb::new(181, 137, 0);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// A color for the label `idx`, so that neighboring labels are easy to tell apart.
+    pub fn pick(idx: usize) -> Self {
+        // Stepping around the hue circle by the golden angle never lands close to earlier
+        // steps for long.
+        let hue = (idx as f64 * 137.507_764) % 360.0;
+        let sector = hue / 60.0;
+        let rising = (255.0 * sector.fract()) as u8;
+        let falling = 255 - rising;
+        match sector as usize {
+            0 => Rgb::new(255, rising, 0),
+            1 => Rgb::new(falling, 255, 0),
+            2 => Rgb::new(0, 255, rising),
+            3 => Rgb::new(0, falling, 255),
+            4 => Rgb::new(rising, 0, 255),
+            _ => Rgb::new(255, 0, falling),
+        }
+    }
+
+    /// The color halfway between this one and `other`.
+    pub fn mix(self, other: Rgb) -> Self {
+        let avg = |a: u8, b: u8| ((a as u16 + b as u16) / 2) as u8;
+        Rgb::new(avg(self.r, other.r), avg(self.g, other.g), avg(self.b, other.b))
+    }
+}
+
+/// A picture of a grid with one color per cell, which can be painted over and written out as
+/// an image or drawn in a terminal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    pixels: Grid<Rgb>,
+}
+
+impl Canvas {
+    /// Colors every cell of the grid through the palette.
+    pub fn new<T>(grid: &Grid<T>, palette: impl FnMut(&T) -> Rgb) -> Self {
+        Canvas {
+            pixels: grid.map(palette),
+        }
+    }
+
+    /// Paints the cells over with `color`. Cells outside of the canvas are left out.
+    pub fn overlay(mut self, cells: impl IntoIterator<Item = TableIdx>, color: Rgb) -> Self {
+        for pos in cells {
+            if let Some(pixel) = self.pixels.get_mut(pos) {
+                *pixel = color;
+            }
+        }
+        self
+    }
+
+    /// Like `overlay`, but lets the colors underneath shine through.
+    pub fn tint(mut self, cells: impl IntoIterator<Item = TableIdx>, color: Rgb) -> Self {
+        for pos in cells {
+            if let Some(pixel) = self.pixels.get_mut(pos) {
+                *pixel = pixel.mix(color);
+            }
+        }
+        self
+    }
+
+    /// The pixels row by row as RGB bytes, with every cell drawn as a square of `scale`
+    /// pixels. Returns the width and height in pixels along with them.
+    pub fn rgb_bytes(&self, scale: usize) -> (usize, usize, Vec<u8>) {
+        let pixels = self.pixels.scale(scale, scale, |pixel| vec![*pixel; scale * scale]);
+        let bytes = pixels.rows().flatten().flat_map(|p| [p.r, p.g, p.b]).collect();
+        (pixels.width(), pixels.height(), bytes)
+    }
+
+    /// Writes a binary PPM image with every cell drawn as a square of `scale` pixels.
+    pub fn write_ppm(&self, mut out: impl Write, scale: usize) -> anyhow::Result<()> {
+        let (width, height, bytes) = self.rgb_bytes(scale);
+        write!(out, "P6\n{} {}\n255\n", width, height)?;
+        out.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Writes a PNG image with every cell drawn as a square of `scale` pixels.
+    pub fn write_png(&self, out: impl Write, scale: usize) -> anyhow::Result<()> {
+        let (width, height, bytes) = self.rgb_bytes(scale);
+        let mut encoder = png::Encoder::new(out, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&bytes)?;
+        Ok(())
+    }
+
+    /// Writes the image to `path`, as PNG or PPM depending on its extension.
+    pub fn save(&self, path: impl AsRef<Path>, scale: usize) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|e| e.to_str());
+        if !matches!(extension, Some("png" | "ppm")) {
+            return Err(anyhow!("{} is neither a .png nor a .ppm file", path.display()));
+        }
+        let out = BufWriter::new(File::create(path)?);
+        match extension {
+            Some("png") => self.write_png(out, scale),
+            _ => self.write_ppm(out, scale),
+        }
+    }
+
+    /// Draws the canvas with ANSI truecolor escapes, two rows of cells per line of text.
+    pub fn to_ansi(&self) -> String {
+        let mut ret = String::new();
+        let rows: Vec<Vec<Rgb>> = self.pixels.rows().map(|row| row.copied().collect()).collect();
+        for pair in rows.chunks(2) {
+            for (x, top) in pair[0].iter().enumerate() {
+                ret += &format!("\x1b[38;2;{};{};{}m", top.r, top.g, top.b);
+                match pair.get(1) {
+                    Some(bottom) => {
+                        let bottom = bottom[x];
+                        ret += &format!("\x1b[48;2;{};{};{}m", bottom.r, bottom.g, bottom.b)
+                    }
+                    None => ret += "\x1b[49m",
+                }
+                ret.push('▀');
+            }
+            ret += "\x1b[0m\n";
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::parse_char_table;
+
+    #[fixture]
+    fn canvas() -> Canvas {
+        let grid: Grid<char> = parse_char_table("#.\n.#\n..\n").unwrap().into();
+        Canvas::new(&grid, |c| if *c == '#' { Rgb::BLACK } else { Rgb::WHITE })
+    }
+
+    #[rstest]
+    fn test_overlay(canvas: Canvas) {
+        let painted = canvas
+            .clone()
+            .overlay([TableIdx::new(1, 0), TableIdx::new(5, 5)], Rgb::RED)
+            .tint([TableIdx::new(0, 0), TableIdx::new(0, 1)], Rgb::WHITE);
+        assert_eq!(painted.pixels[TableIdx::new(1, 0)], Rgb::RED);
+        assert_eq!(painted.pixels[TableIdx::new(0, 0)], Rgb::new(127, 127, 127));
+        assert_eq!(painted.pixels[TableIdx::new(0, 1)], Rgb::WHITE);
+        assert_eq!(painted.pixels[TableIdx::new(1, 1)], canvas.pixels[TableIdx::new(1, 1)]);
+    }
+
+    #[rstest]
+    fn test_ppm(canvas: Canvas) {
+        let mut out = Vec::new();
+        canvas.write_ppm(&mut out, 2).unwrap();
+        let header = b"P6\n4 6\n255\n";
+        assert_eq!(&out[..header.len()], header);
+        assert_eq!(out.len(), header.len() + 4 * 6 * 3);
+        // The first row is two black pixels followed by two white ones.
+        assert_eq!(out[header.len()..header.len() + 12], [0, 0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255]);
+    }
+
+    #[rstest]
+    fn test_png(canvas: Canvas) {
+        let mut out = Vec::new();
+        canvas.write_png(&mut out, 3).unwrap();
+        let decoder = png::Decoder::new(out.as_slice());
+        let reader = decoder.read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (6, 9));
+    }
+
+    #[rstest]
+    fn test_save_rejects_unknown_format(canvas: Canvas) {
+        let err = canvas.save("picture.gif", 1).unwrap_err();
+        assert_eq!(err.to_string(), "picture.gif is neither a .png nor a .ppm file");
+    }
+
+    #[rstest]
+    fn test_ansi(canvas: Canvas) {
+        let ansi = canvas.to_ansi();
+        let lines: Vec<&str> = ansi.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("\x1b[38;2;0;0;0m\x1b[48;2;255;255;255m▀"));
+        assert!(lines[1].contains("\x1b[49m▀"));
+        assert!(lines.iter().all(|line| line.ends_with("\x1b[0m")));
+    }
+
+    #[rstest]
+    fn test_pick_distinct() {
+        let colors: Vec<Rgb> = (0..8).map(Rgb::pick).collect();
+        for (i, a) in colors.iter().enumerate() {
+            assert!(!colors[i + 1..].contains(a));
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::anyhow;
+
+use itertools::Itertools;
+
+use super::{cast_ray, Dir4, Dir8, Grid, PlaneIdx, Symmetry, TableIdx};
+
+/// An occurrence of one of the words given to `find_words`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WordMatch {
+    /// Index of the word in the list it was searched with.
+    pub word: usize,
+    /// Position of the first letter.
+    pub start: TableIdx,
+    /// Reading direction.
+    pub dir: Dir8,
+}
+
+/// An occurrence of the pattern given to `find_pattern`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PatternMatch {
+    /// Position of the top left corner of the transformed pattern.
+    pub origin: TableIdx,
+    /// How the pattern was turned or mirrored to match.
+    pub symmetry: Symmetry,
+}
+
+/// Aho-Corasick automaton over a set of words.
+struct Automaton {
+    goto: Vec<HashMap<char, usize>>,
+    fail: Vec<usize>,
+    /// Indices of the words ending in each state, including those reached by failing over.
+    output: Vec<Vec<usize>>,
+}
+
+impl Automaton {
+    fn new<S: AsRef<str>>(words: &[S]) -> Self {
+        let mut goto = vec![HashMap::new()];
+        let mut output = vec![Vec::new()];
+        for (idx, word) in words.iter().enumerate() {
+            let mut state = 0;
+            for c in word.as_ref().chars() {
+                state = match goto[state].get(&c) {
+                    Some(next) => *next,
+                    None => {
+                        let next = goto.len();
+                        goto.push(HashMap::new());
+                        output.push(Vec::new());
+                        goto[state].insert(c, next);
+                        next
+                    }
+                };
+            }
+            output[state].push(idx);
+        }
+
+        let mut fail = vec![0; goto.len()];
+        let mut queue: VecDeque<usize> = goto[0].values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            for (c, next) in goto[state].clone() {
+                let mut fallback = fail[state];
+                while fallback != 0 && !goto[fallback].contains_key(&c) {
+                    fallback = fail[fallback];
+                }
+                fail[next] = goto[fallback].get(&c).copied().filter(|f| *f != next).unwrap_or(0);
+                let inherited = output[fail[next]].clone();
+                output[next].extend(inherited);
+                queue.push_back(next);
+            }
+        }
+        Automaton { goto, fail, output }
+    }
+
+    fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(next) = self.goto[state].get(&c) {
+                return *next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.fail[state];
+        }
+    }
+}
+
+/// Finds every occurrence of the words read in any of `dirs`, reading every line of the grid
+/// once per direction. Overlapping occurrences are all found. Fails on empty words, which
+/// have no first letter to report.
+pub fn find_words<S: AsRef<str>>(
+    grid: &Grid<char>,
+    words: &[S],
+    dirs: impl IntoIterator<Item = Dir8>,
+) -> anyhow::Result<Vec<WordMatch>> {
+    if let Some(word) = words.iter().position(|w| w.as_ref().is_empty()) {
+        return Err(anyhow!("word {} is empty", word));
+    }
+    let automaton = Automaton::new(words);
+    let lengths: Vec<usize> = words.iter().map(|w| w.as_ref().chars().count()).collect();
+    let mut ret = Vec::new();
+    for dir in dirs {
+        let step = dir.to_vec();
+        // Every line starts at a cell whose predecessor is off the grid.
+        let starts = grid.indexed_iter().filter(|(pos, _)| grid.shift(*pos, -step).is_none());
+        for (start, _) in starts {
+            let mut state = 0;
+            for (pos, c) in cast_ray(grid, start.as_(), step) {
+                state = automaton.step(state, *c);
+                for word in &automaton.output[state] {
+                    let back = step * (lengths[*word] as isize - 1);
+                    let start = (pos.as_::<isize>() - back).as_();
+                    ret.push(WordMatch { word: *word, start, dir });
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Whether the pattern matches with its top left corner at `origin`, `wildcard` matching any
+/// cell.
+fn matches_at(grid: &Grid<char>, pattern: &Grid<char>, wildcard: char, origin: PlaneIdx) -> bool {
+    pattern.rows().enumerate().all(|(y, row)| {
+        let window: Vec<&char> = cast_ray(grid, origin + PlaneIdx::new(0, y as isize), Dir4::Right.to_vec())
+            .max_len(pattern.width())
+            .map(|(_, c)| c)
+            .collect();
+        window.len() == pattern.width() && row.zip(window).all(|(p, c)| *p == wildcard || p == c)
+    })
+}
+
+/// Finds every place the pattern matches when turned or mirrored by one of `symmetries`,
+/// `wildcard` matching any cell. Symmetries that leave the pattern as it is are only
+/// reported once, with the first of them.
+pub fn find_pattern(
+    grid: &Grid<char>,
+    pattern: &Grid<char>,
+    wildcard: char,
+    symmetries: impl IntoIterator<Item = Symmetry>,
+) -> Vec<PatternMatch> {
+    let variants = symmetries
+        .into_iter()
+        .map(|symmetry| (pattern.transform(symmetry), symmetry))
+        .unique_by(|(variant, _)| variant.clone());
+    let mut ret = Vec::new();
+    for (variant, symmetry) in variants {
+        for (origin, _) in grid.indexed_iter() {
+            if matches_at(grid, &variant, wildcard, origin.as_()) {
+                ret.push(PatternMatch { origin, symmetry });
+            }
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::parse_char_table;
+
+    fn grid(input: &str) -> Grid<char> {
+        parse_char_table(input).unwrap().into()
+    }
+
+    #[rstest]
+    fn overlapping_words() {
+        let found = find_words(&grid("SHERS\n"), &["HE", "SHE", "HERS", "ERS"], [Dir8::Right]).unwrap();
+        let found: Vec<(usize, usize)> = found.iter().map(|m| (m.word, m.start.x)).sorted().collect();
+        assert_eq!(found, [(0, 1), (1, 0), (2, 1), (3, 2)]);
+    }
+
+    #[rstest]
+    fn words_in_all_directions() {
+        let table = grid("XMAS\nMM..\nA.A.\nS..S\n");
+        let found = find_words(&table, &["XMAS"], Dir8::ALL).unwrap();
+        let dirs: Vec<Dir8> = found.iter().map(|m| m.dir).sorted_by_key(|d| *d as usize).collect();
+        assert_eq!(dirs, [Dir8::Right, Dir8::DownRight, Dir8::Down]);
+        assert!(found.iter().all(|m| m.start == TableIdx::new(0, 0)));
+
+        let backwards: Vec<(TableIdx, Dir8)> = find_words(&table, &["SAMX"], [Dir8::Left, Dir8::UpLeft])
+            .unwrap()
+            .iter()
+            .map(|m| (m.start, m.dir))
+            .collect();
+        assert_eq!(backwards, [(TableIdx::new(3, 0), Dir8::Left), (TableIdx::new(3, 3), Dir8::UpLeft)]);
+    }
+
+    #[rstest]
+    fn reject_empty_words() {
+        let err = find_words(&grid("AB\n"), &["A", ""], Dir8::ALL).unwrap_err();
+        assert_eq!(err.to_string(), "word 1 is empty");
+    }
+
+    #[rstest]
+    fn patterns_with_symmetries() {
+        let table = grid("M.S.\n.A..\nM.S.\n.M.M\n..A.\n.S.S\n");
+        let pattern = grid("M.S\n.A.\nM.S\n");
+        let found = find_pattern(&table, &pattern, '.', Symmetry::ALL);
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&PatternMatch {
+            origin: TableIdx::new(0, 0),
+            symmetry: Symmetry::Identity
+        }));
+        assert!(found.contains(&PatternMatch {
+            origin: TableIdx::new(1, 3),
+            symmetry: Symmetry::RotateRight
+        }));
+
+        assert_eq!(find_pattern(&table, &pattern, '.', [Symmetry::Identity]).len(), 1);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use ndarray::Array2;
+
+use super::{Cells, Grid, PlaneIdx, TableDir, TableIdx};
+
+/// An unbounded grid that only stores the cells that were set. It may grow in any direction,
+/// including to negative positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<PlaneIdx, T>,
+}
+
+impl<T> SparseGrid<T> {
+    /// All cells that were set with their position, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (PlaneIdx, &T)> + '_ {
+        self.cells.iter().map(|(pos, cell)| (*pos, cell))
+    }
+
+    /// Smallest and largest corner of the area holding all cells, both inclusive.
+    pub fn bounds(&self) -> Option<(PlaneIdx, PlaneIdx)> {
+        let mut positions = self.iter().map(|(pos, _)| pos);
+        let first = positions.next()?;
+        Some(positions.fold((first, first), |(min, max), pos| {
+            (PlaneIdx::partial_min(min, pos), PlaneIdx::partial_max(max, pos))
+        }))
+    }
+
+    /// The cells inside `bounds` as a dense grid, with `empty` wherever no cell was set.
+    /// Returns the grid together with the position its top left cell stands for.
+    pub fn to_grid(&self, empty: T) -> (Grid<T>, PlaneIdx)
+    where
+        T: Clone,
+    {
+        let Some((min, max)) = self.bounds() else {
+            return (Array2::from_elem((0, 0), empty).into(), PlaneIdx::zero());
+        };
+        let size = (max - min).as_::<usize>() + 1;
+        let grid = Array2::from_shape_fn((size.y, size.x), |(y, x)| {
+            let pos = min + TableIdx::new(x, y).as_();
+            self.cells.get(&pos).unwrap_or(&empty).clone()
+        });
+        (grid.into(), min)
+    }
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> FromIterator<(PlaneIdx, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (PlaneIdx, T)>>(iter: I) -> Self {
+        Self {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Sets the given cells, growing the grid as needed.
+impl<T> Extend<(PlaneIdx, T)> for SparseGrid<T> {
+    fn extend<I: IntoIterator<Item = (PlaneIdx, T)>>(&mut self, iter: I) {
+        self.cells.extend(iter);
+    }
+}
+
+impl<T> Cells for SparseGrid<T> {
+    type Pos = PlaneIdx;
+    type Cell = T;
+
+    fn shift(&self, pos: PlaneIdx, step: TableDir) -> Option<PlaneIdx> {
+        Some(pos + step)
+    }
+
+    fn get(&self, pos: PlaneIdx) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    fn locate(&self, point: PlaneIdx) -> Option<PlaneIdx> {
+        Some(point)
+    }
+
+    fn extent(&self) -> Option<(PlaneIdx, PlaneIdx)> {
+        // Without cells the area is empty, its smallest corner lies past the largest.
+        Some(self.bounds().unwrap_or((PlaneIdx::zero(), -PlaneIdx::one())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::{cast_ray, Dir4, PPCharTable};
+
+    #[fixture]
+    fn sparse() -> SparseGrid<char> {
+        [((-2, 1), 'A'), ((-1, 1), 'B'), ((0, 1), 'C'), ((1, -1), 'D')]
+            .into_iter()
+            .map(|((x, y), c)| (PlaneIdx::new(x, y), c))
+            .collect()
+    }
+
+    #[rstest]
+    fn test_bounds(sparse: SparseGrid<char>) {
+        assert_eq!(sparse.bounds(), Some((PlaneIdx::new(-2, -1), PlaneIdx::new(1, 1))));
+        assert_eq!(SparseGrid::<char>::default().bounds(), None);
+        assert_eq!(cast_ray(&SparseGrid::<char>::default(), PlaneIdx::zero(), TableDir::new(1, 0)).count(), 0);
+    }
+
+    #[rstest]
+    fn test_grows(mut sparse: SparseGrid<char>) {
+        sparse.extend([(PlaneIdx::new(-5, 0), 'E'), (PlaneIdx::new(-5, 0), 'F')]);
+        assert_eq!(sparse.get(PlaneIdx::new(-5, 0)), Some(&'F'));
+        assert_eq!(sparse.bounds().unwrap().0, PlaneIdx::new(-5, -1));
+        assert_eq!(sparse.iter().count(), 5);
+    }
+
+    #[rstest]
+    fn test_neighbors_and_rays(sparse: SparseGrid<char>) {
+        let neighbors: String = sparse.neighbors8(PlaneIdx::new(0, 0)).map(|(_, c)| c).collect();
+        assert_eq!(neighbors, "DCB");
+        let dirs: Vec<Dir4> = sparse.neighbors4(PlaneIdx::new(-1, 1)).with_dirs().map(|(dir, _, _)| dir).collect();
+        assert_eq!(dirs, [Dir4::Right, Dir4::Left]);
+        let ray: String = cast_ray(&sparse, PlaneIdx::new(0, 1), TableDir::new(-1, 0)).map(|(_, c)| *c).collect();
+        assert_eq!(ray, "CBA");
+    }
+
+    #[rstest]
+    fn test_render(sparse: SparseGrid<char>) {
+        let (grid, origin) = sparse.to_grid('.');
+        assert_eq!(origin, PlaneIdx::new(-2, -1));
+        assert_eq!(format!("{:?}", PPCharTable::from(&grid)), "\n...D\n....\nABC.\n");
+    }
+}
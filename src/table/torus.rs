@@ -0,0 +1,130 @@
+use std::ops::{Index, IndexMut};
+
+use anyhow::{ensure, Error};
+
+use super::{Cells, Grid, PlaneIdx, TableDir, TableIdx};
+
+/// A grid whose opposite edges are glued together, so every step stays on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Torus<T> {
+    grid: Grid<T>,
+}
+
+impl<T> Torus<T> {
+    pub fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    /// The position on the torus that `pos` ends up at after wrapping around.
+    pub fn wrap(&self, pos: PlaneIdx) -> TableIdx {
+        TableIdx::new(
+            pos.x.rem_euclid(self.width() as isize) as usize,
+            pos.y.rem_euclid(self.height() as isize) as usize,
+        )
+    }
+
+    /// The cells laid out flat, with the seams at the borders.
+    pub fn as_grid(&self) -> &Grid<T> {
+        &self.grid
+    }
+}
+
+impl<T> Cells for Torus<T> {
+    type Pos = TableIdx;
+    type Cell = T;
+
+    fn shift(&self, pos: TableIdx, step: TableDir) -> Option<TableIdx> {
+        Some(self.wrap(pos.as_() + step))
+    }
+
+    fn get(&self, pos: TableIdx) -> Option<&T> {
+        self.grid.get(self.wrap(pos.as_()))
+    }
+
+    fn locate(&self, point: PlaneIdx) -> Option<TableIdx> {
+        Some(self.wrap(point))
+    }
+
+    /// Unbounded, as every point of the plane is somewhere on the torus.
+    fn extent(&self) -> Option<(PlaneIdx, PlaneIdx)> {
+        None
+    }
+}
+
+impl<T> TryFrom<Grid<T>> for Torus<T> {
+    type Error = Error;
+
+    fn try_from(grid: Grid<T>) -> Result<Self, Error> {
+        ensure!(grid.width() > 0 && grid.height() > 0, "a torus needs at least one cell");
+        Ok(Self { grid })
+    }
+}
+
+impl<T> Index<TableIdx> for Torus<T> {
+    type Output = T;
+
+    fn index(&self, idx: TableIdx) -> &T {
+        &self.grid[self.wrap(idx.as_())]
+    }
+}
+
+impl<T> IndexMut<TableIdx> for Torus<T> {
+    fn index_mut(&mut self, idx: TableIdx) -> &mut T {
+        let idx = self.wrap(idx.as_());
+        &mut self.grid[idx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::{cast_ray, Dir4};
+
+    use ndarray::{arr2, Array2};
+
+    #[fixture]
+    fn torus() -> Torus<char> {
+        Grid::from(arr2(&[['A', 'B', 'C'], ['D', 'E', 'F']])).try_into().unwrap()
+    }
+
+    #[rstest]
+    #[case(PlaneIdx::new(3, 0), TableIdx::new(0, 0))]
+    #[case(PlaneIdx::new(-1, -1), TableIdx::new(2, 1))]
+    #[case(PlaneIdx::new(-7, 5), TableIdx::new(2, 1))]
+    fn test_wrap(torus: Torus<char>, #[case] pos: PlaneIdx, #[case] expected: TableIdx) {
+        assert_eq!(torus.wrap(pos), expected)
+    }
+
+    #[rstest]
+    fn test_neighbors(torus: Torus<char>) {
+        let corner: String = torus.neighbors4(TableIdx::new(0, 0)).map(|(_, c)| c).collect();
+        assert_eq!(corner, "DBDC");
+        let dirs: Vec<Dir4> = torus.neighbors4(TableIdx::new(2, 1)).with_dirs().map(|(dir, _, _)| dir).collect();
+        assert_eq!(dirs, Dir4::ALL);
+    }
+
+    #[rstest]
+    fn test_ray_wraps(torus: Torus<char>) {
+        let ray: String = cast_ray(&torus, PlaneIdx::new(1, 1), TableDir::new(1, 1)).map(|(_, c)| *c).take(7).collect();
+        assert_eq!(ray, "ECDBFAE");
+    }
+
+    #[rstest]
+    fn test_index_wraps(mut torus: Torus<char>) {
+        torus[TableIdx::new(4, 3)] = 'X';
+        assert_eq!(torus.as_grid()[TableIdx::new(1, 1)], 'X');
+        assert_eq!(torus[TableIdx::new(1, 1)], 'X');
+    }
+
+    #[test]
+    fn reject_empty() {
+        let empty: Grid<char> = Grid::from(Array2::from_elem((0, 3), '.'));
+        assert!(Torus::try_from(empty).is_err());
+    }
+}
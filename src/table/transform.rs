@@ -0,0 +1,181 @@
+use ndarray::Array2;
+
+use super::{Grid, TableIdx};
+
+/// One of the eight ways to turn or mirror a grid onto itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    Identity,
+    /// Quarter turn clockwise.
+    RotateRight,
+    RotateHalf,
+    /// Quarter turn counterclockwise.
+    RotateLeft,
+    /// Mirrors left and right.
+    FlipHorizontal,
+    /// Mirrors along the diagonal through the top left corner.
+    Transpose,
+    /// Mirrors top and bottom.
+    FlipVertical,
+    /// Mirrors along the diagonal through the top right corner.
+    AntiTranspose,
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::RotateRight,
+        Symmetry::RotateHalf,
+        Symmetry::RotateLeft,
+        Symmetry::FlipHorizontal,
+        Symmetry::Transpose,
+        Symmetry::FlipVertical,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// The symmetry that undoes this one.
+    pub fn inverse(self) -> Self {
+        match self {
+            Symmetry::RotateRight => Symmetry::RotateLeft,
+            Symmetry::RotateLeft => Symmetry::RotateRight,
+            other => other,
+        }
+    }
+
+    fn swaps_axes(self) -> bool {
+        matches!(
+            self,
+            Symmetry::RotateRight | Symmetry::RotateLeft | Symmetry::Transpose | Symmetry::AntiTranspose
+        )
+    }
+
+    /// Where `idx` of a grid with the given size ends up once transformed.
+    pub fn map(self, idx: TableIdx, width: usize, height: usize) -> TableIdx {
+        let (x, y) = (idx.x, idx.y);
+        let (right, bottom) = (width - 1 - x, height - 1 - y);
+        match self {
+            Symmetry::Identity => TableIdx::new(x, y),
+            Symmetry::RotateRight => TableIdx::new(bottom, x),
+            Symmetry::RotateHalf => TableIdx::new(right, bottom),
+            Symmetry::RotateLeft => TableIdx::new(y, right),
+            Symmetry::FlipHorizontal => TableIdx::new(right, y),
+            Symmetry::Transpose => TableIdx::new(y, x),
+            Symmetry::FlipVertical => TableIdx::new(x, bottom),
+            Symmetry::AntiTranspose => TableIdx::new(bottom, right),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn transform(&self, symmetry: Symmetry) -> Grid<T> {
+        let (width, height) = if symmetry.swaps_axes() {
+            (self.height(), self.width())
+        } else {
+            (self.width(), self.height())
+        };
+        let inverse = symmetry.inverse();
+        Array2::from_shape_fn((height, width), |(y, x)| {
+            self[inverse.map(TableIdx::new(x, y), width, height)].clone()
+        })
+        .into()
+    }
+
+    /// Repeats the grid `across` times side by side and `down` times on top of each other.
+    pub fn tile(&self, across: usize, down: usize) -> Grid<T> {
+        Array2::from_shape_fn((down * self.height(), across * self.width()), |(y, x)| {
+            self[self.untile_idx(TableIdx::new(x, y))].clone()
+        })
+        .into()
+    }
+
+    /// The cell a position of a tiled copy of the grid was taken from, see `tile`.
+    pub fn untile_idx(&self, idx: TableIdx) -> TableIdx {
+        TableIdx::new(idx.x % self.width(), idx.y % self.height())
+    }
+
+    /// Replaces every cell with a block of `width` by `height` cells, given row by row by
+    /// `expand`. Panics if an expansion doesn't fill its block exactly.
+    pub fn scale<I>(&self, width: usize, height: usize, mut expand: impl FnMut(&T) -> I) -> Grid<T>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let blocks = self.map(|cell| {
+            let block: Vec<T> = expand(cell).into_iter().collect();
+            assert_eq!(block.len(), width * height, "expansion doesn't fit a {}x{} block", width, height);
+            block
+        });
+        Array2::from_shape_fn((height * self.height(), width * self.width()), |(y, x)| {
+            let (idx, offset) = unscale_idx(TableIdx::new(x, y), width, height);
+            blocks[idx][offset.y * width + offset.x].clone()
+        })
+        .into()
+    }
+}
+
+/// The cell a position of a grid scaled by blocks of `width` by `height` came from, and the
+/// offset inside of that cell's block, see `Grid::scale`.
+pub fn unscale_idx(idx: TableIdx, width: usize, height: usize) -> (TableIdx, TableIdx) {
+    (
+        TableIdx::new(idx.x / width, idx.y / height),
+        TableIdx::new(idx.x % width, idx.y % height),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    use crate::table::parse_char_table;
+
+    fn grid(input: &str) -> Grid<char> {
+        parse_char_table(input).unwrap().into()
+    }
+
+    fn render(grid: &Grid<char>) -> String {
+        grid.rows().map(|row| row.collect::<String>() + "\n").collect()
+    }
+
+    #[rstest]
+    #[case(Symmetry::Identity, "ABC\nDEF\n")]
+    #[case(Symmetry::RotateRight, "DA\nEB\nFC\n")]
+    #[case(Symmetry::RotateHalf, "FED\nCBA\n")]
+    #[case(Symmetry::RotateLeft, "CF\nBE\nAD\n")]
+    #[case(Symmetry::FlipHorizontal, "CBA\nFED\n")]
+    #[case(Symmetry::Transpose, "AD\nBE\nCF\n")]
+    #[case(Symmetry::FlipVertical, "DEF\nABC\n")]
+    #[case(Symmetry::AntiTranspose, "FC\nEB\nDA\n")]
+    fn test_transform(#[case] symmetry: Symmetry, #[case] expected: &str) {
+        let original = grid("ABC\nDEF\n");
+        let transformed = original.transform(symmetry);
+        assert_eq!(render(&transformed), expected);
+        assert_eq!(transformed.transform(symmetry.inverse()), original);
+        for (idx, cell) in original.indexed_iter() {
+            assert_eq!(transformed[symmetry.map(idx, original.width(), original.height())], *cell);
+        }
+    }
+
+    #[rstest]
+    fn test_tile() {
+        let original = grid("AB\nCD\n");
+        let tiled = original.tile(3, 2);
+        assert_eq!(render(&tiled), "ABABAB\nCDCDCD\nABABAB\nCDCDCD\n");
+        assert_eq!(original.untile_idx(TableIdx::new(5, 2)), TableIdx::new(1, 0));
+    }
+
+    #[rstest]
+    fn test_scale() {
+        let original = grid("AB\nCD\n");
+        let scaled = original.scale(2, 1, |c| [*c, c.to_ascii_lowercase()]);
+        assert_eq!(render(&scaled), "AaBb\nCcDd\n");
+        let tall = original.scale(1, 2, |c| [*c, '.']);
+        assert_eq!(render(&tall), "AB\n..\nCD\n..\n");
+        assert_eq!(unscale_idx(TableIdx::new(3, 1), 2, 1), (TableIdx::new(1, 1), TableIdx::new(1, 0)));
+    }
+
+    #[rstest]
+    #[should_panic]
+    fn test_scale_mismatch() {
+        grid("AB\n").scale(2, 2, |c| [*c]);
+    }
+}
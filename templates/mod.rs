@@ -44,6 +44,12 @@ impl FromStr for Day {
     }
 }
 
+impl ToPuzzleString for Day {
+    fn to_puzzle_string(&self) -> String {
+        String::new()
+    }
+}
+
 fn parse_day(input: &str) -> IResult<&str, Day> {
     Ok(("", Day {}))
 }
@@ -53,6 +59,8 @@ mod tests {
     use super::*;
     use rstest::*;
 
+    use crate::fuzz::check_parser;
+
     #[fixture]
     fn example() -> &'static str {
         "\
@@ -70,4 +78,15 @@ mod tests {
         let result: Day = example.parse().unwrap();
         assert_eq!(result, example_parsed)
     }
+
+    #[rstest]
+    fn round_trip(example: &'static str) {
+        let parsed: Day = example.parse().unwrap();
+        assert_eq!(parsed.to_puzzle_string(), example)
+    }
+
+    #[rstest]
+    fn fuzz_parser(example: &'static str) {
+        check_parser::<Day>(example)
+    }
 }